@@ -1,3 +1,13 @@
 fn main() {
-    tauri_build::build()
+    #[cfg(feature = "gui")]
+    tauri_build::build();
+
+    // tauri-build sets `dev` for the app, headless debug builds use the dev folder as well
+    #[cfg(not(feature = "gui"))]
+    {
+        println!("cargo:rustc-check-cfg=cfg(dev)");
+        if std::env::var("PROFILE").as_deref() == Ok("debug") {
+            println!("cargo:rustc-cfg=dev");
+        }
+    }
 }
@@ -1,4 +1,7 @@
+use chrono::Weekday;
+use octocrab::models::AuthorAssociation;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::{
     app_data_v1::{AppConfigV1, AppDataV1},
@@ -9,6 +12,102 @@ fn default_repo_config() -> Vec<RepoConfigV2> {
     Vec::new()
 }
 
+fn default_mute_rules() -> Vec<MuteRuleV2> {
+    Vec::new()
+}
+
+// Notify about what needs action by default, not about every review request or pending PR.
+// Listed in the order notifications are sent.
+pub fn default_category_notification_settings() -> Vec<CategoryNotificationSettingV2> {
+    [
+        (PullRequestCategoryV2::Rereview, true),
+        (PullRequestCategoryV2::MineApproved, true),
+        (PullRequestCategoryV2::MineChangesRequested, true),
+        (PullRequestCategoryV2::ReviewMissing, true),
+        (PullRequestCategoryV2::ReviewRequested, false),
+        (PullRequestCategoryV2::MinePending, false),
+    ]
+    .into_iter()
+    .map(|(category, enabled)| CategoryNotificationSettingV2 { category, enabled })
+    .collect()
+}
+
+fn default_notification_settings() -> NotificationSettingsV2 {
+    NotificationSettingsV2 {
+        categories: default_category_notification_settings(),
+        repo_overrides: Vec::new(),
+    }
+}
+
+fn default_digest() -> DigestSettingsV2 {
+    DigestSettingsV2 {
+        enabled: false,
+        cadence: DigestCadenceV2::Interval { minutes: 60 },
+    }
+}
+
+fn default_webhooks() -> Vec<WebhookConfigV2> {
+    Vec::new()
+}
+
+fn default_email_digest() -> EmailDigestSettingsV2 {
+    EmailDigestSettingsV2 {
+        enabled: false,
+        smtp_host: "".to_string(),
+        smtp_port: 587,
+        starttls: true,
+        username: None,
+        password: None,
+        password_source: None,
+        from: "".to_string(),
+        to: Vec::new(),
+        cadence: DigestCadenceV2::Times {
+            times: vec!["08:00".to_string()],
+        },
+    }
+}
+
+fn default_reminder_rules() -> Vec<ReminderRuleV2> {
+    Vec::new()
+}
+
+fn default_working_hours() -> WorkingHoursV2 {
+    WorkingHoursV2 {
+        days: vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ],
+        start: "09:00".to_string(),
+        end: "17:00".to_string(),
+    }
+}
+
+fn default_http_api() -> HttpApiSettingsV2 {
+    HttpApiSettingsV2 {
+        enabled: false,
+        port: 7879,
+        token: Uuid::new_v4().simple().to_string(),
+    }
+}
+
+fn default_team_policy_path() -> Option<String> {
+    None
+}
+
+fn default_rereview_on_new_commits() -> bool {
+    true
+}
+
+fn default_quiet_hours() -> QuietHoursV2 {
+    QuietHoursV2 {
+        enabled: false,
+        windows: Vec::new(),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfigV2 {
     pub version: u8,
@@ -16,14 +115,187 @@ pub struct AppConfigV2 {
     pub username: Option<String>,
     #[serde(default = "default_repo_config")]
     pub repo_config: Vec<RepoConfigV2>,
+    #[serde(default = "default_mute_rules")]
+    pub mute_rules: Vec<MuteRuleV2>,
+    #[serde(default = "default_notification_settings")]
+    pub notification_settings: NotificationSettingsV2,
+    #[serde(default = "default_quiet_hours")]
+    pub quiet_hours: QuietHoursV2,
+    #[serde(default = "default_digest")]
+    pub digest: DigestSettingsV2,
+    #[serde(default = "default_webhooks")]
+    pub webhooks: Vec<WebhookConfigV2>,
+    #[serde(default = "default_email_digest")]
+    pub email_digest: EmailDigestSettingsV2,
+    #[serde(default = "default_reminder_rules")]
+    pub reminder_rules: Vec<ReminderRuleV2>,
+    #[serde(default = "default_working_hours")]
+    pub working_hours: WorkingHoursV2,
+    #[serde(default = "default_http_api")]
+    pub http_api: HttpApiSettingsV2,
+    #[serde(default = "default_team_policy_path")]
+    pub team_policy_path: Option<String>,
+    // Move PRs I reviewed to Rereview when commits are pushed after my review
+    #[serde(default = "default_rereview_on_new_commits")]
+    pub rereview_on_new_commits: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RepoConfigV2 {
     pub repo_name: String,
+    // None falls back to the team policy or the default
+    #[serde(default)]
+    pub needed_approvals: Option<usize>,
+    // Checked in order against the PR's base branch before `needed_approvals`
+    #[serde(default = "default_branch_rules")]
+    pub branch_rules: Vec<BranchRuleV2>,
+    // When any of these are set, only approvals from matching reviewers count. Users
+    // accept wildcards, teams are `org/team` or a team of the repository owner, and
+    // associations are GitHub's `author_association`, e.g. MEMBER or OWNER.
+    #[serde(default = "default_approvers")]
+    pub approvers: Vec<String>,
+    #[serde(default = "default_approvers")]
+    pub approver_teams: Vec<String>,
+    #[serde(default = "default_approver_associations")]
+    pub approver_associations: Vec<AuthorAssociation>,
+    // Whether approvals on older commits stop counting after a push, None follows the
+    // team policy or GitHub
+    #[serde(default)]
+    pub dismiss_stale_approvals: Option<bool>,
+}
+
+// `branch` accepts wildcards, e.g. `release/*`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchRuleV2 {
+    pub branch: String,
     pub needed_approvals: usize,
 }
 
+// Every criterion that is set has to match for the rule to mute a PR. Repository,
+// author and label accept wildcards, e.g. `*[bot]` or `myorg/*`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MuteRuleV2 {
+    pub id: Uuid,
+    pub repo_name: Option<String>,
+    pub author: Option<String>,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSettingsV2 {
+    pub categories: Vec<CategoryNotificationSettingV2>,
+    pub repo_overrides: Vec<RepoNotificationOverrideV2>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryNotificationSettingV2 {
+    pub category: PullRequestCategoryV2,
+    pub enabled: bool,
+}
+
+// Repo overrides take precedence over the category settings, the repo name accepts wildcards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoNotificationOverrideV2 {
+    pub repo_name: String,
+    pub categories: Vec<CategoryNotificationSettingV2>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHoursV2 {
+    pub enabled: bool,
+    pub windows: Vec<QuietHoursWindowV2>,
+}
+
+// Times are "HH:MM" in the local timezone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHoursWindowV2 {
+    pub days: Vec<Weekday>,
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestSettingsV2 {
+    pub enabled: bool,
+    pub cadence: DigestCadenceV2,
+}
+
+// Either every N minutes, or at fixed "HH:MM" local times each day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DigestCadenceV2 {
+    Interval { minutes: u64 },
+    Times { times: Vec<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfigV2 {
+    pub id: Uuid,
+    pub name: String,
+    pub url: String,
+    pub format: WebhookFormatV2,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WebhookFormatV2 {
+    Slack,
+    Teams,
+    Generic,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailDigestSettingsV2 {
+    pub enabled: bool,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub starttls: bool,
+    pub username: Option<String>,
+    // Only read to move passwords saved by older builds out of config.json
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub password_source: Option<SmtpPasswordSourceV2>,
+    pub from: String,
+    pub to: Vec<String>,
+    pub cadence: DigestCadenceV2,
+}
+
+// Where the SMTP password is read from when sending, so it's never stored in the config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SmtpPasswordSourceV2 {
+    Env { name: String },
+    File { path: String },
+}
+
+// Served on 127.0.0.1 only, every request has to carry the token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpApiSettingsV2 {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+// Reminds every `interval_hours` working hours while a PR stays in one of the categories,
+// reminders after the first `escalate_after` ones are sent with critical urgency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderRuleV2 {
+    pub id: Uuid,
+    pub categories: Vec<PullRequestCategoryV2>,
+    pub interval_hours: u64,
+    pub escalate_after: u32,
+    pub enabled: bool,
+}
+
+// Times are "HH:MM" in the local timezone. An end at or before the start runs past midnight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkingHoursV2 {
+    pub days: Vec<Weekday>,
+    pub start: String,
+    pub end: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppDataV2 {
     pub version: u8,
@@ -36,7 +308,7 @@ pub struct PullRequestsDataV2 {
     pub pull_requests: Vec<PullRequestItemV2>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum PullRequestCategoryV2 {
     MineApproved,
     MineChangesRequested,
@@ -50,6 +322,38 @@ fn default_is_assigned() -> bool {
     false
 }
 
+fn default_is_muted() -> bool {
+    false
+}
+
+fn default_branch_ref() -> Option<String> {
+    None
+}
+
+fn default_pending_code_owners() -> Vec<Vec<String>> {
+    Vec::new()
+}
+
+fn default_is_code_owner() -> bool {
+    false
+}
+
+fn default_reason() -> Option<String> {
+    None
+}
+
+fn default_branch_rules() -> Vec<BranchRuleV2> {
+    Vec::new()
+}
+
+fn default_approvers() -> Vec<String> {
+    Vec::new()
+}
+
+fn default_approver_associations() -> Vec<AuthorAssociation> {
+    Vec::new()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PullRequestItemV2 {
     pub id: u64,
@@ -64,6 +368,41 @@ pub struct PullRequestItemV2 {
     #[serde(default = "default_is_assigned")]
     pub is_assigned: bool,
     pub category: PullRequestCategoryV2,
+    #[serde(default = "default_is_muted")]
+    pub muted: bool,
+    #[serde(default = "default_branch_ref")]
+    pub base_ref: Option<String>,
+    #[serde(default = "default_branch_ref")]
+    pub head_ref: Option<String>,
+    // CODEOWNERS owner sets still missing an approval, one owner of each set has to approve
+    #[serde(default = "default_pending_code_owners")]
+    pub pending_code_owners: Vec<Vec<String>>,
+    // Someone else's PR that waits on a code owner group you're in
+    #[serde(default = "default_is_code_owner")]
+    pub is_code_owner: bool,
+    // Why the PR is in its category, when that isn't obvious
+    #[serde(default = "default_reason")]
+    pub reason: Option<String>,
+}
+
+pub fn empty_config() -> AppConfigV2 {
+    AppConfigV2 {
+        version: 2,
+        github_token: None,
+        username: None,
+        repo_config: Vec::new(),
+        mute_rules: Vec::new(),
+        notification_settings: default_notification_settings(),
+        quiet_hours: default_quiet_hours(),
+        digest: default_digest(),
+        webhooks: default_webhooks(),
+        email_digest: default_email_digest(),
+        reminder_rules: default_reminder_rules(),
+        working_hours: default_working_hours(),
+        http_api: default_http_api(),
+        team_policy_path: default_team_policy_path(),
+        rereview_on_new_commits: default_rereview_on_new_commits(),
+    }
 }
 
 pub async fn convert_config_to_v2(config: &AppConfigV1) -> AppConfigV2 {
@@ -74,7 +413,7 @@ pub async fn convert_config_to_v2(config: &AppConfigV1) -> AppConfigV2 {
             Some(token) => Some(verify_token(token.clone()).await.unwrap()),
             None => None,
         },
-        repo_config: Vec::new(),
+        ..empty_config()
     };
     config
 }
@@ -1,23 +1,27 @@
 use anyhow::Result;
+use octocrab::models::AuthorAssociation;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tauri::Emitter;
-use tauri::Manager;
 
 use tokio::sync::Mutex;
 
 use crate::app_data::AppConfig;
 use crate::app_data::AppData;
-use crate::app_data::PullRequestCategory;
-use crate::app_data::PullRequestItem;
 use crate::app_data::PullRequestsData;
+use crate::app_data_v2::empty_config;
+use crate::app_data_v2::BranchRuleV2;
 use crate::app_data_v2::RepoConfigV2;
+use crate::event_bus::{AppEvent, EventBus};
 use crate::event_names::AppConfigUpdatedPayload;
 use crate::event_names::AppDataUpdatedPayload;
-use crate::event_names::{EventNames, FilterDataUpdatedPayload};
+use crate::event_names::FilterDataUpdatedPayload;
 use crate::github_service::get_owner_and_repo;
 use crate::github_service::GithubPRWithReviews;
-use crate::pr_predicates::PR_CATEGORIES;
+use crate::metrics::Metrics;
+use crate::notifications::NotificationState;
+use crate::pull_requests::build_pull_requests_data;
+use crate::repo_rules::TeamMembers;
+use crate::team_policy::TeamPolicy;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GithubFilterUpdate {
@@ -26,20 +30,28 @@ pub struct GithubFilterUpdate {
     pub name: String,
 }
 
+// The last poll's answer from GitHub, to categorize PRs again when rules change
+#[derive(Clone)]
+pub struct LastResponse {
+    pub response: Vec<GithubPRWithReviews>,
+    pub team_members: TeamMembers,
+}
+
+#[derive(Clone)]
 pub struct AppState {
     pub data: Arc<Mutex<AppData>>,
     pub config: Arc<Mutex<AppConfig>>,
+    pub notification_state: Arc<Mutex<NotificationState>>,
+    pub metrics: Arc<Mutex<Metrics>>,
+    pub team_policy: Arc<Mutex<TeamPolicy>>,
+    pub last_response: Arc<Mutex<Option<LastResponse>>>,
+    pub events: EventBus,
 }
 
 impl AppState {
     pub fn new() -> Result<Self> {
         Ok(Self {
-            config: Arc::new(Mutex::new(AppConfig {
-                version: 2,
-                github_token: None,
-                username: None,
-                repo_config: Vec::new(),
-            })),
+            config: Arc::new(Mutex::new(empty_config())),
             data: Arc::new(Mutex::new(AppData {
                 version: 2,
                 pull_requests: PullRequestsData {
@@ -47,74 +59,42 @@ impl AppState {
                     pull_requests: Vec::new(),
                 },
             })),
+            notification_state: Arc::new(Mutex::new(NotificationState::default())),
+            metrics: Arc::new(Mutex::new(Metrics::default())),
+            team_policy: Arc::new(Mutex::new(TeamPolicy::default())),
+            last_response: Arc::new(Mutex::new(None)),
+            events: EventBus::new(),
         })
     }
 }
 
-fn get_category_from_reviews(
-    pr_with_reviews: &GithubPRWithReviews,
-    config: &AppConfig,
-) -> PullRequestCategory {
-    for category in PR_CATEGORIES.iter() {
-        if (category.predicate)(pr_with_reviews, config) {
-            return category.category.clone();
-        }
-    }
-    crate::log::error(&format!(
-        "No category found for PR: {}",
-        pr_with_reviews.pr.id
-    ));
-    return PullRequestCategory::MinePending;
-}
-
-fn map_to_app_data(
-    github_pr_with_reviews: &GithubPRWithReviews,
-    config: &AppConfig,
-) -> PullRequestItem {
-    PullRequestItem {
-        id: github_pr_with_reviews.pr.id.to_be(),
-        title: github_pr_with_reviews.pr.title.clone(),
-        url: github_pr_with_reviews.pr.url.to_string(),
-        repository_url: github_pr_with_reviews.pr.repository_url.to_string(),
-        login: github_pr_with_reviews.pr.user.login.clone(),
-        avatar_url: github_pr_with_reviews.pr.user.avatar_url.to_string(),
-        html_url: github_pr_with_reviews.pr.html_url.to_string(),
-        created_at: github_pr_with_reviews.pr.created_at.to_rfc3339(),
-        updated_at: github_pr_with_reviews.pr.updated_at.to_rfc3339(),
-        is_assigned: github_pr_with_reviews
-            .pr
-            .assignees
-            .iter()
-            .any(|a| a.login == config.username.clone().unwrap_or("".to_string())),
-        category: get_category_from_reviews(github_pr_with_reviews, &config),
-    }
-}
-
 pub async fn new_pull_request_response(
-    app_handle: &tauri::AppHandle,
+    state: &AppState,
     response: &Vec<GithubPRWithReviews>,
+    team_members: &TeamMembers,
 ) {
-    let state = app_handle.state::<AppState>();
-    let config = app_handle.state::<AppState>().config.lock().await.clone();
-    let old_data = app_handle.state::<AppState>().data.lock().await.clone();
+    *state.last_response.lock().await = Some(LastResponse {
+        response: response.clone(),
+        team_members: team_members.clone(),
+    });
+
+    let config = state.config.lock().await.clone();
+    let old_data = state.data.lock().await.clone();
 
     let old_pr_data = old_data.pull_requests;
 
-    let new_pr_data = PullRequestsData {
-        last_updated: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-        pull_requests: response
-            .iter()
-            .map(|r| map_to_app_data(r, &config))
-            .collect(),
-    };
+    let team_policy = state.team_policy.lock().await.clone();
+    let new_pr_data = build_pull_requests_data(response, &config, &team_policy, team_members);
 
     {
         let mut data = state.data.lock().await;
         data.pull_requests = new_pr_data.clone();
     }
+    state
+        .metrics
+        .lock()
+        .await
+        .record_pull_requests(&new_pr_data);
 
     let all_repos = new_pr_data
         .pull_requests
@@ -130,11 +110,7 @@ pub async fn new_pull_request_response(
             .find(|r| r.repo_name == repo)
             .cloned();
         if repo_config.is_none() {
-            save_repo_config(repo, 1, app_handle.clone())
-                .await
-                .unwrap_or_else(|e| {
-                    crate::log::error(&format!("Failed to save repo config: {}", e));
-                });
+            update_repo_config(state, repo, None).await;
         }
     }
 
@@ -142,98 +118,107 @@ pub async fn new_pull_request_response(
         new_data: new_pr_data.clone(),
         old_data: old_pr_data.clone(),
     };
-    app_handle
-        .emit(EventNames::FILTER_DATA_UPDATED, payload)
-        .unwrap_or_else(|e| {
-            crate::log::error(&format!("Failed to emit app data updated event: {}", e));
-        });
+    state.events.emit(AppEvent::FilterDataUpdated(payload));
 
-    let new_app_data = app_handle.state::<AppState>().data.lock().await.clone();
+    let new_app_data = state.data.lock().await.clone();
     let payload = AppDataUpdatedPayload { data: new_app_data };
-    app_handle
-        .emit(EventNames::APP_DATA_UPDATED, payload)
-        .unwrap_or_else(|e| {
-            crate::log::error(&format!("Failed to emit app data updated event: {}", e));
-        });
+    state.events.emit(AppEvent::AppDataUpdated(payload));
 }
 
-// Tauri commands
-#[tauri::command]
-pub async fn get_config(state: tauri::State<'_, AppState>) -> Result<AppConfig, String> {
-    let config = state.config.lock().await;
-
-    Ok(AppConfig {
-        version: config.version,
-        username: config.username.clone(),
-        github_token: config.github_token.clone(),
-        repo_config: config.repo_config.clone(),
-    })
+// Without GitHub requests, nothing happens before the first poll
+pub async fn recategorize_last_response(state: &AppState) {
+    let last_response = state.last_response.lock().await.clone();
+    if let Some(last_response) = last_response {
+        new_pull_request_response(state, &last_response.response, &last_response.team_members)
+            .await;
+    }
 }
 
-#[tauri::command]
-pub async fn get_data(state: tauri::State<'_, AppState>) -> Result<AppData, String> {
-    let data = state.data.lock().await;
-    Ok(data.clone())
+pub async fn update_repo_config(
+    state: &AppState,
+    repo_name: String,
+    needed_approvals: Option<usize>,
+) {
+    {
+        let mut config = state.config.lock().await;
+        repo_config_entry(&mut config.repo_config, repo_name).needed_approvals = needed_approvals;
+    }
+
+    emit_config_updated(state).await;
 }
 
-#[tauri::command]
-pub async fn save_repo_config(
+pub async fn update_branch_rules(
+    state: &AppState,
     repo_name: String,
-    needed_approvals: usize,
-    app_handle: tauri::AppHandle,
-) -> Result<(), String> {
-    let state = app_handle.state::<AppState>();
+    branch_rules: Vec<BranchRuleV2>,
+) {
     {
         let mut config = state.config.lock().await;
+        repo_config_entry(&mut config.repo_config, repo_name).branch_rules = branch_rules;
+    }
 
-        config.repo_config = config
-            .repo_config
-            .iter()
-            .filter(|r| r.repo_name != repo_name)
-            .cloned()
-            .collect();
-
-        config.repo_config.push(RepoConfigV2 {
-            repo_name,
-            needed_approvals,
-        });
+    emit_config_updated(state).await;
+    recategorize_last_response(state).await;
+}
+
+pub async fn update_dismiss_stale_approvals(
+    state: &AppState,
+    repo_name: String,
+    dismiss_stale_approvals: Option<bool>,
+) {
+    {
+        let mut config = state.config.lock().await;
+        repo_config_entry(&mut config.repo_config, repo_name).dismiss_stale_approvals =
+            dismiss_stale_approvals;
     }
 
-    app_handle
-        .emit(
-            EventNames::APP_CONFIG_UPDATED,
-            AppConfigUpdatedPayload {
-                config: state.config.lock().await.clone(),
-            },
-        )
-        .unwrap_or_else(|e| {
-            crate::log::error(&format!("Failed to emit app data updated event: {}", e));
-        });
-
-    Ok(())
+    emit_config_updated(state).await;
+    recategorize_last_response(state).await;
 }
 
-#[tauri::command]
-pub async fn save_token(
-    token: String,
-    username: String,
-    app_handle: tauri::AppHandle,
-) -> Result<(), String> {
-    let state = app_handle.state::<AppState>();
+pub async fn update_approvers(
+    state: &AppState,
+    repo_name: String,
+    approvers: Vec<String>,
+    approver_teams: Vec<String>,
+    approver_associations: Vec<AuthorAssociation>,
+) {
     {
         let mut config = state.config.lock().await;
-        config.github_token = Some(token);
-        config.username = Some(username);
+        let repo_config = repo_config_entry(&mut config.repo_config, repo_name);
+        repo_config.approvers = approvers;
+        repo_config.approver_teams = approver_teams;
+        repo_config.approver_associations = approver_associations;
     }
-    app_handle
-        .emit(
-            EventNames::APP_CONFIG_UPDATED,
-            AppConfigUpdatedPayload {
-                config: state.config.lock().await.clone(),
-            },
-        )
-        .unwrap_or_else(|e| {
-            crate::log::error(&format!("Failed to emit app data updated event: {}", e));
-        });
-    Ok(())
+
+    emit_config_updated(state).await;
+    recategorize_last_response(state).await;
+}
+
+fn repo_config_entry(repo_config: &mut Vec<RepoConfigV2>, repo_name: String) -> &mut RepoConfigV2 {
+    let index = match repo_config.iter().position(|r| r.repo_name == repo_name) {
+        Some(index) => index,
+        None => {
+            repo_config.push(RepoConfigV2 {
+                repo_name,
+                needed_approvals: None,
+                branch_rules: Vec::new(),
+                approvers: Vec::new(),
+                approver_teams: Vec::new(),
+                approver_associations: Vec::new(),
+                dismiss_stale_approvals: None,
+            });
+            repo_config.len() - 1
+        }
+    };
+    &mut repo_config[index]
+}
+
+pub async fn emit_config_updated(state: &AppState) {
+    let config = state.config.lock().await.clone();
+    state
+        .events
+        .emit(AppEvent::AppConfigUpdated(AppConfigUpdatedPayload {
+            config,
+        }));
 }
@@ -0,0 +1,5 @@
+// Headless companion to the app, reads the same config and data files
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(pr_sentinel_lib::cli::run(args))
+}
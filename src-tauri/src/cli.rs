@@ -0,0 +1,243 @@
+use crate::{
+    app_data::{PullRequestCategory, PullRequestItem},
+    export::{category_title, group_by_category},
+    file_storage::{load_config, load_data, save_data},
+    github_service::{get_repo_name, GithubClient},
+    pull_requests::{build_pull_requests_data, PULL_REQUEST_QUERY},
+    repo_rules::approver_teams,
+    team_policy::load_configured_team_policy,
+};
+
+const USAGE: &str = "Usage:
+  pr-sentinel list [--category <category>] [--all] [--format table|json|markdown]
+  pr-sentinel refresh
+
+Commands:
+  list      Print the PRs from the last refresh
+  refresh   Fetch PRs from GitHub and update the stored data, through the running
+            app or daemon if there is one
+
+Options:
+  --category <category>  Only show one category, e.g. rereview or review-requested
+  --all                  Include muted PRs
+  --format <format>      Output format, defaults to table
+  --json                 Same as --format json
+  --markdown             Same as --format markdown";
+
+#[derive(Debug, PartialEq)]
+enum OutputFormat {
+    Table,
+    Json,
+    Markdown,
+}
+
+struct ListOptions {
+    category: Option<PullRequestCategory>,
+    include_muted: bool,
+    format: OutputFormat,
+}
+
+// Entry point of the pr-sentinel binary, returns the process exit code
+pub fn run(args: Vec<String>) -> i32 {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start runtime: {}", e);
+            return 1;
+        }
+    };
+
+    let result = match args.first().map(|s| s.as_str()) {
+        Some("list") => parse_list_options(&args[1..]).and_then(|options| {
+            let output = runtime.block_on(list(&options))?;
+            println!("{}", output);
+            Ok(())
+        }),
+        Some("refresh") => runtime
+            .block_on(refresh())
+            .map(|output| println!("{}", output)),
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(format!("Unknown command: {}\n\n{}", command, USAGE)),
+        None => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn parse_list_options(args: &[String]) -> Result<ListOptions, String> {
+    let mut options = ListOptions {
+        category: None,
+        include_muted: false,
+        format: OutputFormat::Table,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--category" => {
+                let value = args.next().ok_or("--category needs a value")?;
+                options.category = Some(parse_category(value)?);
+            }
+            "--format" => {
+                options.format = match args.next().map(|s| s.as_str()) {
+                    Some("table") => OutputFormat::Table,
+                    Some("json") => OutputFormat::Json,
+                    Some("markdown") => OutputFormat::Markdown,
+                    _ => return Err("--format must be table, json or markdown".to_string()),
+                };
+            }
+            "--json" => options.format = OutputFormat::Json,
+            "--markdown" => options.format = OutputFormat::Markdown,
+            "--all" => options.include_muted = true,
+            _ => return Err(format!("Unknown option: {}\n\n{}", arg, USAGE)),
+        }
+    }
+    Ok(options)
+}
+
+// Accepts the category names in any case, with or without dashes or underscores
+fn parse_category(value: &str) -> Result<PullRequestCategory, String> {
+    let normalized = value.to_lowercase().replace(['-', '_'], "");
+    match normalized.as_str() {
+        "mineapproved" => Ok(PullRequestCategory::MineApproved),
+        "minechangesrequested" => Ok(PullRequestCategory::MineChangesRequested),
+        "minepending" => Ok(PullRequestCategory::MinePending),
+        "reviewrequested" => Ok(PullRequestCategory::ReviewRequested),
+        "rereview" => Ok(PullRequestCategory::Rereview),
+        "reviewmissing" => Ok(PullRequestCategory::ReviewMissing),
+        _ => Err(format!("Unknown category: {}", value)),
+    }
+}
+
+async fn list(options: &ListOptions) -> Result<String, String> {
+    let data = load_data().await?;
+    let pull_requests: Vec<PullRequestItem> = data
+        .pull_requests
+        .pull_requests
+        .into_iter()
+        .filter(|pr| options.include_muted || !pr.muted)
+        .filter(|pr| options.category.as_ref().is_none_or(|c| pr.category == *c))
+        .collect();
+
+    match options.format {
+        OutputFormat::Table => Ok(render_table(&pull_requests)),
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(&pull_requests).map_err(|e| e.to_string())
+        }
+        OutputFormat::Markdown => Ok(render_markdown(&pull_requests)),
+    }
+}
+
+async fn refresh() -> Result<String, String> {
+    // A running app or daemon refreshes itself, so only one process writes the data
+    #[cfg(unix)]
+    if let Some(result) = crate::rpc::call_running_instance("refresh").await {
+        result?;
+        let data = crate::rpc::call_running_instance("get_data")
+            .await
+            .ok_or("The running instance went away")??;
+        let data: crate::app_data::AppData =
+            serde_json::from_value(data).map_err(|e| e.to_string())?;
+        return Ok(format!(
+            "Fetched {} PRs",
+            data.pull_requests.pull_requests.len()
+        ));
+    }
+
+    let config = load_config().await?;
+    let Some(github_token) = config.github_token.clone() else {
+        return Err("No github token found, sign in with the app first".to_string());
+    };
+
+    let client = GithubClient::new(github_token);
+    let response = client
+        .search_pull_requests(
+            PULL_REQUEST_QUERY.to_string(),
+            &config.username.clone().unwrap_or_default(),
+        )
+        .await?;
+    let team_policy = load_configured_team_policy(&config);
+    let team_members = client
+        .get_team_members(&approver_teams(&response, &config, &team_policy))
+        .await;
+    let mut data = load_data().await?;
+    data.pull_requests = build_pull_requests_data(&response, &config, &team_policy, &team_members);
+    let count = data.pull_requests.pull_requests.len();
+    save_data(data).await;
+
+    Ok(format!("Fetched {} PRs", count))
+}
+
+fn render_table(pull_requests: &[PullRequestItem]) -> String {
+    if pull_requests.is_empty() {
+        return "No open PRs".to_string();
+    }
+
+    let header = ["CATEGORY", "REPOSITORY", "AUTHOR", "TITLE", "URL"].map(String::from);
+    let mut rows: Vec<[String; 5]> = vec![header];
+    for (_, group) in group_by_category(pull_requests) {
+        for pr in group {
+            rows.push([
+                category_title(&pr.category).to_string(),
+                get_repo_name(&pr.repository_url),
+                pr.login.clone(),
+                pr.title.clone(),
+                pr.html_url.clone(),
+            ]);
+        }
+    }
+
+    let mut widths = [0; 5];
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<String>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn render_markdown(pull_requests: &[PullRequestItem]) -> String {
+    if pull_requests.is_empty() {
+        return "No open PRs".to_string();
+    }
+
+    group_by_category(pull_requests)
+        .iter()
+        .map(|(title, group)| {
+            let lines: Vec<String> = group
+                .iter()
+                .map(|pr| {
+                    format!(
+                        "- [{}]({}) in {} by {}",
+                        pr.title.replace('[', "\\[").replace(']', "\\]"),
+                        pr.html_url,
+                        get_repo_name(&pr.repository_url),
+                        pr.login
+                    )
+                })
+                .collect();
+            format!("## {} ({})\n\n{}", title, group.len(), lines.join("\n"))
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
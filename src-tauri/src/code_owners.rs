@@ -0,0 +1,156 @@
+// GitHub looks for the file in these places, in this order
+pub const CODE_OWNERS_PATHS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+#[derive(Debug, Clone)]
+pub struct CodeOwnersRule {
+    pattern: String,
+    // As written, e.g. `@alice` or `@myorg/backend`
+    owners: Vec<String>,
+}
+
+pub fn parse_code_owners(contents: &str) -> Vec<CodeOwnersRule> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pattern = parts.next()?.to_string();
+            // Owners given by email can't be matched against reviews
+            let owners = parts
+                .filter(|owner| owner.starts_with('@'))
+                .map(|owner| owner.to_string())
+                .collect();
+            Some(CodeOwnersRule { pattern, owners })
+        })
+        .collect()
+}
+
+// The distinct sets of owners covering the changed files, one of each set has to approve.
+// Like on GitHub, the last matching rule wins and a rule without owners leaves a file
+// unowned.
+pub fn owner_groups(rules: &[CodeOwnersRule], changed_files: &[String]) -> Vec<Vec<String>> {
+    let mut groups: Vec<Vec<String>> = Vec::new();
+    for file in changed_files {
+        let Some(rule) = rules
+            .iter()
+            .rev()
+            .find(|rule| pattern_matches(&rule.pattern, file))
+        else {
+            continue;
+        };
+        if !rule.owners.is_empty() && !groups.contains(&rule.owners) {
+            groups.push(rule.owners.clone());
+        }
+    }
+    groups
+}
+
+// Gitignore style patterns: a slash at the start or in the middle anchors the pattern to
+// the repository root, a trailing slash only matches directories. Unlike gitignore, a
+// pattern ending in `/*` only matches the files directly inside the directory.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let directory_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let body = trimmed.trim_start_matches('/');
+    let full_pattern = if trimmed.contains('/') {
+        body.to_string()
+    } else {
+        format!("**/{}", body)
+    };
+
+    let matches_directory = !full_pattern.ends_with('*') && {
+        let within_directory = format!("{}/**", full_pattern);
+        glob_matches(within_directory.as_bytes(), path.as_bytes())
+    };
+    matches_directory || (!directory_only && glob_matches(full_pattern.as_bytes(), path.as_bytes()))
+}
+
+// `*` and `?` stay within a path segment, `**` crosses segments
+fn glob_matches(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            // `**/` also matches no directories at all
+            (0..=path.len()).any(|i| glob_matches(rest, &path[i..]))
+                || rest
+                    .strip_prefix(b"/")
+                    .is_some_and(|rest| glob_matches(rest, path))
+        }
+        [b'*', rest @ ..] => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != b'/')
+            .any(|i| glob_matches(rest, &path[i..])),
+        [b'?', rest @ ..] => {
+            path.first().is_some_and(|c| *c != b'/') && glob_matches(rest, &path[1..])
+        }
+        [c, rest @ ..] => path.first() == Some(c) && glob_matches(rest, &path[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_patterns_like_github() {
+        let cases = [
+            ("*", "README.md", true),
+            ("*", "src/main.rs", true),
+            ("*.js", "src/app.js", true),
+            ("*.js", "src/app.ts", false),
+            ("/docs/", "docs/index.md", true),
+            ("/docs/", "docs/guides/setup.md", true),
+            ("/docs/", "src/docs/index.md", false),
+            ("docs/*", "docs/getting-started.md", true),
+            ("docs/*", "docs/build-app/troubleshooting.md", false),
+            ("docs/*", "src/docs/getting-started.md", false),
+            ("**/logs", "logs/out.log", true),
+            ("**/logs", "build/logs/out.log", true),
+            ("**/logs", "deeply/nested/logs/out.log", true),
+            ("**/logs", "build/logs.txt", false),
+            ("apps/", "apps/web/index.js", true),
+            ("apps/", "src/apps/index.js", true),
+            ("apps/", "apps", false),
+            ("/build/logs/", "build/logs/out.log", true),
+            ("/build/logs/", "src/build/logs/out.log", false),
+            ("src/*.rs", "src/main.rs", true),
+            ("src/*.rs", "src/bin/cli.rs", false),
+        ];
+        for (pattern, path, expected) in cases {
+            assert_eq!(
+                pattern_matches(pattern, path),
+                expected,
+                "{} against {}",
+                pattern,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let rules = parse_code_owners(
+            "# Defaults\n\
+             *       @myorg/everyone\n\
+             *.js    @alice docs@example.com\n\
+             /docs/  @bob @myorg/writers # Documentation\n\
+             /docs/generated/\n",
+        );
+        let changed_files = [
+            "README.md",
+            "src/app.js",
+            "docs/index.md",
+            "docs/generated/api.md",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            owner_groups(&rules, &changed_files),
+            vec![
+                vec!["@myorg/everyone".to_string()],
+                vec!["@alice".to_string()],
+                vec!["@bob".to_string(), "@myorg/writers".to_string()],
+            ]
+        );
+    }
+}
@@ -0,0 +1,412 @@
+// Everything the window calls, thin wrappers over the state shared with the daemon and CLI
+use std::path::Path;
+
+use chrono::Utc;
+use octocrab::models::AuthorAssociation;
+use tauri::Manager;
+
+use crate::{
+    app_data_v2::{
+        BranchRuleV2, DigestSettingsV2, EmailDigestSettingsV2, HttpApiSettingsV2, MuteRuleV2,
+        NotificationSettingsV2, QuietHoursV2, ReminderRuleV2, WebhookConfigV2, WorkingHoursV2,
+    },
+    app_state::{
+        emit_config_updated, recategorize_last_response, update_approvers, update_branch_rules,
+        update_dismiss_stale_approvals, update_repo_config,
+    },
+    email_digest::send_email_digest,
+    export::{render, ExportFormat},
+    notifications::{
+        get_status, pause, resume, send_notification, snooze, DesktopNotification,
+        NotificationResult, NotificationStatus, NotificationUrgency,
+    },
+    polling::refresh_all_filters,
+    settings_file::{
+        apply_settings, read_settings_file, settings_from_config, validate_settings,
+        write_settings_file, ImportMode,
+    },
+    webhook::{render_payload, WebhookChannel},
+    AppConfig, AppData, AppState,
+};
+
+#[tauri::command]
+pub async fn verify_token(token: String) -> Result<String, ()> {
+    crate::verify_token(token).await
+}
+
+#[tauri::command]
+pub async fn get_config(state: tauri::State<'_, AppState>) -> Result<AppConfig, String> {
+    let config = state.config.lock().await;
+    Ok(config.clone())
+}
+
+#[tauri::command]
+pub async fn get_data(state: tauri::State<'_, AppState>) -> Result<AppData, String> {
+    let data = state.data.lock().await;
+    Ok(data.clone())
+}
+
+#[tauri::command]
+pub async fn save_repo_config(
+    repo_name: String,
+    needed_approvals: Option<usize>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    update_repo_config(&state, repo_name, needed_approvals).await;
+    recategorize_last_response(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_branch_rules(
+    repo_name: String,
+    branch_rules: Vec<BranchRuleV2>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    update_branch_rules(&state, repo_name, branch_rules).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_dismiss_stale_approvals(
+    repo_name: String,
+    dismiss_stale_approvals: Option<bool>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    update_dismiss_stale_approvals(&state, repo_name, dismiss_stale_approvals).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_approvers(
+    repo_name: String,
+    approvers: Vec<String>,
+    approver_teams: Vec<String>,
+    approver_associations: Vec<AuthorAssociation>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    update_approvers(
+        &state,
+        repo_name,
+        approvers,
+        approver_teams,
+        approver_associations,
+    )
+    .await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_token(
+    token: String,
+    username: String,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    {
+        let mut config = state.config.lock().await;
+        config.github_token = Some(token);
+        config.username = Some(username);
+    }
+    emit_config_updated(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_mute_rules(
+    mute_rules: Vec<MuteRuleV2>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    {
+        let mut config = state.config.lock().await;
+        config.mute_rules = mute_rules;
+    }
+    emit_config_updated(&state).await;
+    recategorize_last_response(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_notification_settings(
+    notification_settings: NotificationSettingsV2,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    {
+        let mut config = state.config.lock().await;
+        config.notification_settings = notification_settings;
+    }
+    emit_config_updated(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_quiet_hours(
+    quiet_hours: QuietHoursV2,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    crate::quiet_hours::validate_quiet_hours(&quiet_hours)?;
+    let state = app_handle.state::<AppState>();
+    {
+        let mut config = state.config.lock().await;
+        config.quiet_hours = quiet_hours;
+    }
+    emit_config_updated(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_digest_settings(
+    digest: DigestSettingsV2,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    crate::digest::validate_cadence(&digest.cadence)?;
+    let state = app_handle.state::<AppState>();
+    {
+        let mut config = state.config.lock().await;
+        config.digest = digest;
+    }
+    emit_config_updated(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_webhooks(
+    webhooks: Vec<WebhookConfigV2>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    {
+        let mut config = state.config.lock().await;
+        config.webhooks = webhooks;
+    }
+    emit_config_updated(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_email_digest_settings(
+    email_digest: EmailDigestSettingsV2,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    crate::digest::validate_cadence(&email_digest.cadence)?;
+    let state = app_handle.state::<AppState>();
+    let started = {
+        let mut config = state.config.lock().await;
+        let started = email_digest.enabled && !config.email_digest.enabled;
+        config.email_digest = email_digest;
+        started
+    };
+    if started {
+        crate::email_digest::start_email_digest_period(&state).await;
+    }
+    emit_config_updated(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_reminder_settings(
+    reminder_rules: Vec<ReminderRuleV2>,
+    working_hours: WorkingHoursV2,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    crate::reminders::validate_reminder_rules(&reminder_rules)?;
+    crate::reminders::validate_working_hours(&working_hours)?;
+    let state = app_handle.state::<AppState>();
+    {
+        let mut config = state.config.lock().await;
+        config.reminder_rules = reminder_rules;
+        config.working_hours = working_hours;
+    }
+    emit_config_updated(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_http_api_settings(
+    http_api: HttpApiSettingsV2,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    crate::http_api::validate_http_api_settings(&http_api)?;
+    let state = app_handle.state::<AppState>();
+    {
+        let mut config = state.config.lock().await;
+        config.http_api = http_api;
+    }
+    emit_config_updated(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_team_policy_path(
+    team_policy_path: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    {
+        let mut config = state.config.lock().await;
+        config.team_policy_path = team_policy_path;
+    }
+    emit_config_updated(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_rereview_on_new_commits(
+    rereview_on_new_commits: bool,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    {
+        let mut config = state.config.lock().await;
+        config.rereview_on_new_commits = rereview_on_new_commits;
+    }
+    emit_config_updated(&state).await;
+    recategorize_last_response(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn refresh(app_handle: tauri::AppHandle) {
+    // An attached GUI leaves polling to the daemon
+    #[cfg(unix)]
+    if crate::daemon::request_refresh() {
+        return;
+    }
+    let state = app_handle.state::<AppState>().inner().clone();
+    refresh_all_filters(state).await;
+}
+
+// Writes the open PRs, without muted ones, as CSV, Markdown or JSON
+#[tauri::command]
+pub async fn export_pull_requests(
+    path: String,
+    format: ExportFormat,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let pull_requests_data = state.data.lock().await.pull_requests.clone();
+    let output = render(&pull_requests_data, &format, &Utc::now())?;
+    std::fs::write(&path, output).map_err(|e| e.to_string())?;
+    crate::log::info(&format!("Exported PRs to {}", path));
+    Ok(())
+}
+
+// The Markdown checklist for the clipboard, without muted PRs
+#[tauri::command]
+pub async fn copy_pull_requests_as_markdown(
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let state = app_handle.state::<AppState>();
+    let pull_requests_data = state.data.lock().await.pull_requests.clone();
+    render(&pull_requests_data, &ExportFormat::Markdown, &Utc::now())
+}
+
+#[tauri::command]
+pub async fn export_settings(path: String, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let config = state.config.lock().await.clone();
+    let settings = settings_from_config(&config);
+    write_settings_file(Path::new(&path), &settings)?;
+    crate::log::info(&format!("Exported settings to {}", path));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn import_settings(
+    path: String,
+    mode: ImportMode,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let settings = read_settings_file(Path::new(&path))?;
+    validate_settings(&settings)?;
+    let email_digest_started = {
+        let mut config = state.config.lock().await;
+        let was_enabled = config.email_digest.enabled;
+        apply_settings(&mut config, settings, &mode);
+        config.email_digest.enabled && !was_enabled
+    };
+    if email_digest_started {
+        crate::email_digest::start_email_digest_period(&state).await;
+    }
+    emit_config_updated(&state).await;
+    recategorize_last_response(&state).await;
+    crate::log::info(&format!("Imported settings from {} ({:?})", path, mode));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn test_notification(app_handle: tauri::AppHandle) -> Result<NotificationResult, ()> {
+    let notification = DesktopNotification {
+        title: "Test Notification".to_string(),
+        body: "This is a test notification".to_string(),
+        urgency: NotificationUrgency::Normal,
+        icon: "dialog-information",
+        replace_key: None,
+        action: None,
+    };
+    let state = app_handle.state::<AppState>();
+    let result = send_notification(&state, &notification).await;
+
+    return match result {
+        Ok(s) => Ok(NotificationResult { status: s }),
+        Err(e) => Ok(NotificationResult { status: e }),
+    };
+}
+
+#[tauri::command]
+pub async fn get_notification_status(
+    app_handle: tauri::AppHandle,
+) -> Result<NotificationStatus, String> {
+    Ok(get_status(&app_handle.state::<AppState>()).await)
+}
+
+#[tauri::command]
+pub async fn pause_notifications(minutes: u64, app_handle: tauri::AppHandle) -> Result<(), String> {
+    pause(&app_handle.state::<AppState>(), minutes).await
+}
+
+#[tauri::command]
+pub async fn resume_notifications(app_handle: tauri::AppHandle) -> Result<(), String> {
+    resume(&app_handle.state::<AppState>()).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn snooze_pull_request(
+    id: u64,
+    minutes: u64,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    snooze(&app_handle.state::<AppState>(), id, minutes).await
+}
+
+#[tauri::command]
+pub async fn test_webhook(webhook: WebhookConfigV2) -> Result<NotificationResult, ()> {
+    let payload = render_payload(&webhook.format, "Test Notification", &[]);
+    let result = WebhookChannel::new(webhook).post(&payload).await;
+
+    return match result {
+        Ok(s) => Ok(NotificationResult { status: s }),
+        Err(e) => Ok(NotificationResult { status: e }),
+    };
+}
+
+#[tauri::command]
+pub async fn test_email_digest(app_handle: tauri::AppHandle) -> Result<NotificationResult, ()> {
+    let state = app_handle.state::<AppState>();
+    let settings = state.config.lock().await.email_digest.clone();
+    let data = state.data.lock().await.pull_requests.clone();
+    let result = send_email_digest(&settings, &data).await;
+
+    return match result {
+        Ok(s) => Ok(NotificationResult { status: s }),
+        Err(e) => Ok(NotificationResult { status: e }),
+    };
+}
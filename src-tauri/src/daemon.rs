@@ -0,0 +1,349 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{unix::OwnedWriteHalf, UnixListener, UnixStream},
+    sync::mpsc,
+};
+
+use crate::{
+    event_bus::{AppEvent, EventOrigin},
+    event_names::{
+        AppConfigUpdatedPayload, AppDataUpdatedPayload, NotificationStateUpdatedPayload,
+    },
+    file_storage::get_daemon_socket_path,
+    rpc::bind_socket,
+    AppState,
+};
+
+const DAEMON_RECONNECT_SECONDS: u64 = 30;
+
+// The GUI side of an attached connection, events from the daemon are marked with it
+const DAEMON_CONNECTION: u64 = 0;
+
+// Config, data and notification state are sent field by field, so a change on one side
+// doesn't overwrite a field the other side changed at the same time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum SyncedState {
+    Config,
+    Data,
+    NotificationState,
+}
+
+// Messages are sent as one JSON object per line in both directions
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum DaemonMessage {
+    Event {
+        event: AppEvent,
+    },
+    Fields {
+        state: SyncedState,
+        fields: Map<String, Value>,
+    },
+    Refresh,
+}
+
+// The value of each field the other side of a connection last heard of
+#[derive(Default)]
+struct SyncedFields(HashMap<SyncedState, Map<String, Value>>);
+
+impl SyncedFields {
+    // Only the fields that changed are sent, None when the other side is up to date
+    fn outgoing(&mut self, event: AppEvent) -> Option<DaemonMessage> {
+        let Some((state, value)) = synced_fields(&event) else {
+            return Some(DaemonMessage::Event { event });
+        };
+        let known = self.0.entry(state).or_default();
+        let fields: Map<String, Value> = value
+            .into_iter()
+            .filter(|(name, value)| known.get(name) != Some(value))
+            .collect();
+        if fields.is_empty() {
+            return None;
+        }
+        known.extend(fields.clone());
+        Some(DaemonMessage::Fields { state, fields })
+    }
+
+    fn received(&mut self, state: SyncedState, fields: &Map<String, Value>) {
+        self.0.entry(state).or_default().extend(fields.clone());
+    }
+}
+
+fn synced_fields(event: &AppEvent) -> Option<(SyncedState, Map<String, Value>)> {
+    let (state, value) = match event {
+        AppEvent::AppConfigUpdated(payload) => {
+            (SyncedState::Config, serde_json::to_value(&payload.config))
+        }
+        AppEvent::AppDataUpdated(payload) => {
+            (SyncedState::Data, serde_json::to_value(&payload.data))
+        }
+        AppEvent::NotificationStateUpdated(payload) => (
+            SyncedState::NotificationState,
+            serde_json::to_value(&payload.state),
+        ),
+        _ => return None,
+    };
+    match value {
+        Ok(Value::Object(fields)) => Some((state, fields)),
+        _ => None,
+    }
+}
+
+static DAEMON_SENDER: Mutex<Option<mpsc::UnboundedSender<DaemonMessage>>> = Mutex::new(None);
+
+// Runs polling, storage and notifications without a window, started with `--daemon`.
+// A GUI started later attaches over a Unix socket instead of polling on its own.
+pub fn run() {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start runtime: {}", e);
+            return;
+        }
+    };
+    runtime.block_on(async {
+        let state = AppState::new().expect("Failed to initialize app state");
+        crate::file_storage::load_state(state.clone()).await;
+
+        let listener = match bind_socket(&get_daemon_socket_path()).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                crate::log::error(&format!("Failed to start daemon: {}", e));
+                eprintln!("Failed to start daemon: {}", e);
+                return;
+            }
+        };
+        tokio::spawn(serve(listener, state.clone()));
+        #[cfg(target_os = "linux")]
+        tokio::spawn(crate::linux_notifications::listen_for_actions(
+            state.clone(),
+        ));
+
+        crate::log::info("Daemon started");
+        crate::start_background_jobs(&state);
+        std::future::pending::<()>().await;
+    });
+}
+
+async fn serve(listener: UnixListener, state: AppState) {
+    let mut next_connection = DAEMON_CONNECTION + 1;
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                crate::log::info("GUI attached to daemon");
+                tokio::spawn(handle_connection(
+                    stream,
+                    state.clone(),
+                    EventOrigin::Connection(next_connection),
+                ));
+                next_connection += 1;
+            }
+            Err(e) => crate::log::error(&format!("Failed to accept connection: {}", e)),
+        }
+    }
+}
+
+async fn handle_connection(stream: UnixStream, state: AppState, origin: EventOrigin) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut events = state.events.subscribe();
+    let mut synced = SyncedFields::default();
+
+    // Start the GUI off with the daemon's view of everything
+    for event in current_state_events(&state).await {
+        let Some(message) = synced.outgoing(event) else {
+            continue;
+        };
+        if write_message(&mut writer, &message).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Ok(Some(line)) = line else {
+                    break;
+                };
+                match serde_json::from_str::<DaemonMessage>(&line) {
+                    Ok(DaemonMessage::Event { event }) => state.events.emit_from(event, origin),
+                    Ok(DaemonMessage::Fields { state: synced_state, fields }) => {
+                        synced.received(synced_state, &fields);
+                        apply_remote_fields(&state, synced_state, fields, origin).await;
+                    }
+                    Ok(DaemonMessage::Refresh) => {
+                        tokio::spawn(crate::polling::refresh_all_filters(
+                            state.clone(),
+                        ));
+                    }
+                    Err(e) => crate::log::error(&format!("Invalid daemon message: {}", e)),
+                }
+            }
+            Some(bus_event) = events.recv() => {
+                if bus_event.origin == origin {
+                    continue;
+                }
+                let Some(message) = synced.outgoing(bus_event.event) else {
+                    continue;
+                };
+                if write_message(&mut writer, &message).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    crate::log::info("GUI detached from daemon");
+}
+
+async fn current_state_events(state: &AppState) -> Vec<AppEvent> {
+    vec![
+        AppEvent::AppConfigUpdated(AppConfigUpdatedPayload {
+            config: state.config.lock().await.clone(),
+        }),
+        AppEvent::AppDataUpdated(AppDataUpdatedPayload {
+            data: state.data.lock().await.clone(),
+        }),
+        AppEvent::NotificationStateUpdated(NotificationStateUpdatedPayload {
+            state: state.notification_state.lock().await.clone(),
+        }),
+    ]
+}
+
+// Takes over the fields changed on the other side, keeping the rest, and passes the
+// resulting state on locally
+async fn apply_remote_fields(
+    state: &AppState,
+    synced_state: SyncedState,
+    fields: Map<String, Value>,
+    origin: EventOrigin,
+) {
+    let event = match synced_state {
+        SyncedState::Config => {
+            let mut config = state.config.lock().await;
+            with_fields(&*config, fields).map(|updated| {
+                *config = updated;
+                AppEvent::AppConfigUpdated(AppConfigUpdatedPayload {
+                    config: config.clone(),
+                })
+            })
+        }
+        SyncedState::Data => {
+            let mut data = state.data.lock().await;
+            with_fields(&*data, fields).map(|updated| {
+                *data = updated;
+                AppEvent::AppDataUpdated(AppDataUpdatedPayload { data: data.clone() })
+            })
+        }
+        SyncedState::NotificationState => {
+            let mut notification_state = state.notification_state.lock().await;
+            with_fields(&*notification_state, fields).map(|updated| {
+                *notification_state = updated;
+                AppEvent::NotificationStateUpdated(NotificationStateUpdatedPayload {
+                    state: notification_state.clone(),
+                })
+            })
+        }
+    };
+    match event {
+        Ok(event) => state.events.emit_from(event, origin),
+        Err(e) => crate::log::error(&format!("Invalid {:?} from daemon: {}", synced_state, e)),
+    }
+}
+
+fn with_fields<T: Serialize + DeserializeOwned>(
+    value: &T,
+    fields: Map<String, Value>,
+) -> Result<T, String> {
+    let mut value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    let Value::Object(current) = &mut value else {
+        return Err("not an object".to_string());
+    };
+    current.extend(fields);
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+async fn write_message(writer: &mut OwnedWriteHalf, message: &DaemonMessage) -> Result<(), String> {
+    let mut line = serde_json::to_string(message).map_err(|e| e.to_string())?;
+    line.push('\n');
+    writer
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn connect() -> Option<UnixStream> {
+    let socket_path = get_daemon_socket_path();
+    if !socket_path.exists() {
+        return None;
+    }
+    UnixStream::connect(socket_path).await.ok()
+}
+
+// Resolves once a daemon accepts a connection again
+pub async fn wait_for_daemon() -> UnixStream {
+    let mut interval = tokio::time::interval(Duration::from_secs(DAEMON_RECONNECT_SECONDS));
+    loop {
+        interval.tick().await;
+        if let Some(stream) = connect().await {
+            return stream;
+        }
+    }
+}
+
+// Keeps the GUI in sync with the daemon until the connection closes
+pub async fn attach(stream: UnixStream, state: AppState) {
+    let origin = EventOrigin::Connection(DAEMON_CONNECTION);
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut events = state.events.subscribe();
+    let (sender, mut outgoing) = mpsc::unbounded_channel();
+    *DAEMON_SENDER.lock().unwrap() = Some(sender.clone());
+    let mut synced = SyncedFields::default();
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Ok(Some(line)) = line else {
+                    break;
+                };
+                match serde_json::from_str::<DaemonMessage>(&line) {
+                    Ok(DaemonMessage::Event { event }) => state.events.emit_from(event, origin),
+                    Ok(DaemonMessage::Fields { state: synced_state, fields }) => {
+                        synced.received(synced_state, &fields);
+                        apply_remote_fields(&state, synced_state, fields, origin).await;
+                    }
+                    Ok(DaemonMessage::Refresh) => {}
+                    Err(e) => crate::log::error(&format!("Invalid daemon message: {}", e)),
+                }
+            }
+            Some(bus_event) = events.recv() => {
+                if bus_event.origin != EventOrigin::Local {
+                    continue;
+                }
+                if let Some(message) = synced.outgoing(bus_event.event) {
+                    let _ = sender.send(message);
+                }
+            }
+            Some(message) = outgoing.recv() => {
+                if write_message(&mut writer, &message).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    *DAEMON_SENDER.lock().unwrap() = None;
+}
+
+// Returns false when no daemon is attached
+pub fn request_refresh() -> bool {
+    DAEMON_SENDER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|sender| sender.send(DaemonMessage::Refresh).is_ok())
+}
@@ -0,0 +1,171 @@
+use chrono::{DateTime, TimeZone};
+
+use crate::{
+    app_data_v2::DigestCadenceV2,
+    notifications::{format_category_counts, DigestEntry},
+    quiet_hours::parse_time,
+};
+
+const DIGEST_TOP_TITLES: usize = 3;
+
+// An interval of 0 minutes would send a digest on every tick
+pub fn validate_cadence(cadence: &DigestCadenceV2) -> Result<(), String> {
+    match cadence {
+        DigestCadenceV2::Interval { minutes: 0 } => {
+            Err("Digest interval must be at least 1 minute".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn is_digest_due<Tz: TimeZone>(
+    cadence: &DigestCadenceV2,
+    last_sent: u64,
+    now: &DateTime<Tz>,
+) -> bool {
+    let now_seconds = now.timestamp() as u64;
+    match cadence {
+        DigestCadenceV2::Interval { minutes } => {
+            now_seconds.saturating_sub(last_sent) >= minutes.saturating_mul(60)
+        }
+        DigestCadenceV2::Times { times } => times
+            .iter()
+            .filter_map(|time| parse_time(time))
+            .filter_map(|time| {
+                now.timezone()
+                    .from_local_datetime(&now.date_naive().and_time(time))
+                    .earliest()
+            })
+            .map(|scheduled| scheduled.timestamp() as u64)
+            .any(|scheduled| scheduled <= now_seconds && last_sent < scheduled),
+    }
+}
+
+pub fn format_digest(digest_entries: &[DigestEntry]) -> String {
+    let pull_requests: Vec<_> = digest_entries
+        .iter()
+        .map(|e| (&e.category, &e.pull_request))
+        .collect();
+    let mut lines = format_category_counts(&pull_requests);
+
+    let mut latest_entries: Vec<&DigestEntry> = digest_entries.iter().collect();
+    latest_entries.sort_by_key(|e| std::cmp::Reverse(e.changed_at));
+    let top_titles: Vec<&str> = latest_entries
+        .iter()
+        .take(DIGEST_TOP_TITLES)
+        .map(|e| e.pull_request.title.as_str())
+        .collect();
+
+    if !top_titles.is_empty() {
+        lines.push("".to_string());
+        lines.extend(top_titles.iter().map(|t| t.to_string()));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::FixedOffset;
+
+    use super::*;
+    use crate::{app_data::PullRequestCategory, test_support::pull_request};
+
+    fn at(date_time: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_str(&format!("{} +01:00", date_time), "%Y-%m-%d %H:%M %z").unwrap()
+    }
+
+    fn timestamp(date_time: &str) -> u64 {
+        at(date_time).timestamp() as u64
+    }
+
+    #[test]
+    fn digest_is_due_on_cadence() {
+        let hourly = DigestCadenceV2::Interval { minutes: 60 };
+        let twice_a_day = DigestCadenceV2::Times {
+            times: vec!["09:00".to_string(), "17:00".to_string()],
+        };
+        let cases = [
+            (&hourly, 0, "2024-03-18 10:00", true),
+            (
+                &hourly,
+                timestamp("2024-03-18 09:30"),
+                "2024-03-18 10:00",
+                false,
+            ),
+            (
+                &hourly,
+                timestamp("2024-03-18 09:00"),
+                "2024-03-18 10:00",
+                true,
+            ),
+            (&twice_a_day, 0, "2024-03-18 10:00", true),
+            (&twice_a_day, 0, "2024-03-18 08:00", false),
+            (
+                &twice_a_day,
+                timestamp("2024-03-17 17:30"),
+                "2024-03-18 08:59",
+                false,
+            ),
+            (
+                &twice_a_day,
+                timestamp("2024-03-17 17:30"),
+                "2024-03-18 09:00",
+                true,
+            ),
+            (
+                &twice_a_day,
+                timestamp("2024-03-18 09:00"),
+                "2024-03-18 16:59",
+                false,
+            ),
+            (
+                &twice_a_day,
+                timestamp("2024-03-18 09:00"),
+                "2024-03-18 17:01",
+                true,
+            ),
+        ];
+        for (cadence, last_sent, now, expected) in cases {
+            assert_eq!(
+                is_digest_due(cadence, last_sent, &at(now)),
+                expected,
+                "{:?} last sent at {} checked at {}",
+                cadence,
+                last_sent,
+                now
+            );
+        }
+    }
+
+    #[test]
+    fn formats_empty_digest() {
+        assert_eq!(format_digest(&[]), "");
+    }
+
+    #[test]
+    fn formats_counts_and_latest_titles() {
+        let entry =
+            |id: u64, title: &str, category: PullRequestCategory, changed_at: u64| DigestEntry {
+                pull_request: pull_request(id, title, "ReviewRequested"),
+                category,
+                changed_at,
+            };
+        let entries = [
+            entry(1, "Fix login", PullRequestCategory::ReviewRequested, 10),
+            entry(2, "Bump deps", PullRequestCategory::ReviewRequested, 40),
+            entry(3, "Add export", PullRequestCategory::MineApproved, 30),
+            entry(1, "Fix login", PullRequestCategory::ReviewRequested, 20),
+            entry(4, "Old change", PullRequestCategory::Rereview, 5),
+        ];
+        assert_eq!(
+            format_digest(&entries),
+            "1 PRs to re-review\n\
+             1 PRs approved\n\
+             2 PRs to review\n\
+             \n\
+             Bump deps\n\
+             Add export\n\
+             Fix login"
+        );
+    }
+}
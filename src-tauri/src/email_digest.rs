@@ -0,0 +1,336 @@
+use lettre::{
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::{
+    app_data_v2::{EmailDigestSettingsV2, SmtpPasswordSourceV2},
+    digest::is_digest_due,
+    github_service::get_repo_name,
+    notifications::{persist_notification_state, NOTIFICATION_CATEGORIES},
+    AppState, PullRequestItem, PullRequestsData,
+};
+
+const EMAIL_SUBJECT: &str = "PR Sentinel digest";
+
+pub async fn send_email_digest_if_due(state: &AppState) {
+    let config = state.config.lock().await.clone();
+    if !config.email_digest.enabled {
+        return;
+    }
+    {
+        let mut notification_state = state.notification_state.lock().await;
+        // Enabled without going through the settings, e.g. by editing config.json
+        if notification_state.last_email_digest_sent == 0 {
+            notification_state.last_email_digest_sent = chrono::Local::now().timestamp() as u64;
+            drop(notification_state);
+            persist_notification_state(state).await;
+            return;
+        }
+        if !is_digest_due(
+            &config.email_digest.cadence,
+            notification_state.last_email_digest_sent,
+            &chrono::Local::now(),
+        ) {
+            return;
+        }
+        notification_state.last_email_digest_sent = chrono::Local::now().timestamp() as u64;
+    }
+    persist_notification_state(state).await;
+
+    let data = state.data.lock().await.pull_requests.clone();
+    match send_email_digest(&config.email_digest, &data).await {
+        Ok(s) => crate::log::info(&format!("Email digest sent: {}", s)),
+        Err(e) => crate::log::error(&format!("Failed to send email digest: {}", e)),
+    }
+}
+
+// Counts the first period from when the digest was turned on, instead of sending right away
+pub async fn start_email_digest_period(state: &AppState) {
+    state.notification_state.lock().await.last_email_digest_sent =
+        chrono::Local::now().timestamp() as u64;
+    persist_notification_state(state).await;
+}
+
+pub async fn send_email_digest(
+    settings: &EmailDigestSettingsV2,
+    data: &PullRequestsData,
+) -> Result<String, String> {
+    let from: Mailbox = settings.from.parse().map_err(|e| format!("{}", e))?;
+    let mut builder = Message::builder().from(from).subject(EMAIL_SUBJECT);
+    for to in settings.to.iter() {
+        let to: Mailbox = to.parse().map_err(|e| format!("{}", e))?;
+        builder = builder.to(to);
+    }
+
+    let groups = group_by_category(data);
+    let email = builder
+        .multipart(MultiPart::alternative_plain_html(
+            render_text(&groups),
+            render_html(&groups),
+        ))
+        .map_err(|e| e.to_string())?;
+
+    // STARTTLS can be turned off to deliver to a local SMTP sink
+    let mut transport = if settings.starttls {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.smtp_host)
+            .map_err(|e| e.to_string())?
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.smtp_host)
+    }
+    .port(settings.smtp_port);
+    let password = settings
+        .password_source
+        .as_ref()
+        .map(read_smtp_password)
+        .transpose()?;
+    if let (Some(username), Some(password)) = (&settings.username, password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+
+    let response = transport
+        .build()
+        .send(email)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(format!("SMTP server responded with {}", response.code()))
+}
+
+// A trailing newline in a password file isn't part of the password
+fn read_smtp_password(source: &SmtpPasswordSourceV2) -> Result<String, String> {
+    match source {
+        SmtpPasswordSourceV2::Env { name } => std::env::var(name)
+            .map_err(|e| format!("Failed to read SMTP password from ${}: {}", name, e)),
+        SmtpPasswordSourceV2::File { path } => std::fs::read_to_string(path)
+            .map(|password| password.trim_end_matches(['\r', '\n']).to_string())
+            .map_err(|e| format!("Failed to read SMTP password from {}: {}", path, e)),
+    }
+}
+
+fn group_by_category(data: &PullRequestsData) -> Vec<(&'static str, Vec<&PullRequestItem>)> {
+    NOTIFICATION_CATEGORIES
+        .iter()
+        .map(|category_notification| {
+            (
+                category_notification.title,
+                data.pull_requests
+                    .iter()
+                    .filter(|pr| !pr.muted && pr.category == category_notification.category)
+                    .collect::<Vec<&PullRequestItem>>(),
+            )
+        })
+        .filter(|(_, pull_requests)| !pull_requests.is_empty())
+        .collect()
+}
+
+fn render_text(groups: &[(&str, Vec<&PullRequestItem>)]) -> String {
+    if groups.is_empty() {
+        return "No open pull requests.\n".to_string();
+    }
+    let mut text = String::new();
+    for (title, pull_requests) in groups.iter() {
+        text.push_str(&format!("{} ({})\n", title, pull_requests.len()));
+        for pr in pull_requests.iter() {
+            text.push_str(&format!(
+                "- {} [{}] by {}\n  {}\n",
+                pr.title,
+                get_repo_name(&pr.repository_url),
+                pr.login,
+                pr.html_url
+            ));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn render_html(groups: &[(&str, Vec<&PullRequestItem>)]) -> String {
+    if groups.is_empty() {
+        return "<p>No open pull requests.</p>".to_string();
+    }
+    let mut html = String::new();
+    for (title, pull_requests) in groups.iter() {
+        html.push_str(&format!(
+            "<h3>{} ({})</h3>\n<ul>\n",
+            escape_html(title),
+            pull_requests.len()
+        ));
+        for pr in pull_requests.iter() {
+            html.push_str(&format!(
+                "<li><a href=\"{}\">{}</a> in {} by {}</li>\n",
+                escape_html(&pr.html_url),
+                escape_html(&pr.title),
+                escape_html(&get_repo_name(&pr.repository_url)),
+                escape_html(&pr.login)
+            ));
+        }
+        html.push_str("</ul>\n");
+    }
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+        sync::oneshot,
+    };
+
+    use super::*;
+    use crate::{app_data_v2::DigestCadenceV2, test_support::pull_request};
+
+    fn data() -> PullRequestsData {
+        PullRequestsData {
+            last_updated: 0,
+            pull_requests: vec![
+                pull_request(1, "Fix <login> & logout", "ReviewRequested"),
+                pull_request(2, "Bump deps", "Rereview"),
+                PullRequestItem {
+                    muted: true,
+                    ..pull_request(3, "Muted", "ReviewRequested")
+                },
+            ],
+        }
+    }
+
+    // Local SMTP sink that accepts one message and hands over its data
+    async fn smtp_sink() -> (u16, oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = oneshot::channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut sender = Some(sender);
+            writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let command = line.to_uppercase();
+                let reply: &[u8] = if command.starts_with("DATA") {
+                    writer.write_all(b"354 End data with .\r\n").await.unwrap();
+                    let mut message = String::new();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        if line == "." {
+                            break;
+                        }
+                        message.push_str(&line);
+                        message.push('\n');
+                    }
+                    if let Some(sender) = sender.take() {
+                        let _ = sender.send(message);
+                    }
+                    b"250 Queued\r\n"
+                } else if command.starts_with("QUIT") {
+                    writer.write_all(b"221 Bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    b"250 OK\r\n"
+                };
+                writer.write_all(reply).await.unwrap();
+            }
+        });
+        (port, receiver)
+    }
+
+    #[test]
+    fn groups_unmuted_pull_requests_in_notification_order() {
+        let data = data();
+        let groups = group_by_category(&data);
+
+        let titles: Vec<(&str, Vec<&str>)> = groups
+            .iter()
+            .map(|(title, pull_requests)| {
+                (
+                    *title,
+                    pull_requests.iter().map(|pr| pr.title.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                ("PRs to re-review", vec!["Bump deps"]),
+                ("PRs to review", vec!["Fix <login> & logout"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_plain_text() {
+        let data = data();
+        let text = render_text(&group_by_category(&data));
+
+        assert_eq!(
+            text,
+            "PRs to re-review (1)\n\
+             - Bump deps [octo/app] by alice\n  https://github.com/octo/app/pull/2\n\n\
+             PRs to review (1)\n\
+             - Fix <login> & logout [octo/app] by alice\n  https://github.com/octo/app/pull/1\n\n"
+        );
+        assert_eq!(render_text(&[]), "No open pull requests.\n");
+    }
+
+    #[test]
+    fn renders_escaped_html() {
+        let data = data();
+        let html = render_html(&group_by_category(&data));
+
+        assert!(html.contains("<h3>PRs to review (1)</h3>"));
+        assert!(html.contains(
+            "<li><a href=\"https://github.com/octo/app/pull/1\">Fix &lt;login&gt; &amp; logout</a> in octo/app by alice</li>"
+        ));
+        assert!(!html.contains("Muted"));
+    }
+
+    #[test]
+    fn reads_password_file_without_trailing_newline() {
+        let path = std::env::temp_dir().join(format!("smtp_password_{}", std::process::id()));
+        std::fs::write(&path, "hunter2\n").unwrap();
+
+        let password = read_smtp_password(&SmtpPasswordSourceV2::File {
+            path: path.to_string_lossy().to_string(),
+        });
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(password, Ok("hunter2".to_string()));
+    }
+
+    #[tokio::test]
+    async fn sends_digest_to_smtp_server() {
+        let (port, message) = smtp_sink().await;
+        let settings = EmailDigestSettingsV2 {
+            enabled: true,
+            smtp_host: "127.0.0.1".to_string(),
+            smtp_port: port,
+            starttls: false,
+            username: None,
+            password: None,
+            password_source: None,
+            from: "PR Sentinel <sentinel@example.com>".to_string(),
+            to: vec!["alice@example.com".to_string()],
+            cadence: DigestCadenceV2::Times {
+                times: vec!["08:00".to_string()],
+            },
+        };
+
+        let result = send_email_digest(&settings, &data()).await;
+
+        assert_eq!(result, Ok("SMTP server responded with 250".to_string()));
+        let message = message.await.unwrap();
+        assert!(message.contains("Subject: PR Sentinel digest"));
+        assert!(message.contains("To: alice@example.com"));
+        assert!(message.contains("multipart/alternative"));
+        assert!(message.contains("Bump deps [octo/app] by alice"));
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::event_names::{
+    AppConfigUpdatedPayload, AppDataUpdatedPayload, DesktopNotificationPayload, EventNames,
+    FilterDataUpdatedPayload, FocusCategoryPayload, NotificationStateUpdatedPayload,
+};
+
+const CHANNEL_CAPACITY: usize = 64;
+
+// Everything that used to be a Tauri event. Polling, storage and notifications only talk
+// through the bus so they can run without a window, the GUI forwards events to the webview.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", content = "payload")]
+pub enum AppEvent {
+    FilterDataUpdated(FilterDataUpdatedPayload),
+    AppDataUpdated(AppDataUpdatedPayload),
+    AppConfigUpdated(AppConfigUpdatedPayload),
+    NotificationStateUpdated(NotificationStateUpdatedPayload),
+    PollingError(serde_json::Value),
+    FocusCategory(FocusCategoryPayload),
+    ShowNotification(DesktopNotificationPayload),
+}
+
+impl AppEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppEvent::FilterDataUpdated(_) => EventNames::FILTER_DATA_UPDATED,
+            AppEvent::AppDataUpdated(_) => EventNames::APP_DATA_UPDATED,
+            AppEvent::AppConfigUpdated(_) => EventNames::APP_CONFIG_UPDATED,
+            AppEvent::NotificationStateUpdated(_) => EventNames::NOTIFICATION_STATE_UPDATED,
+            AppEvent::PollingError(_) => EventNames::POLLING_ERROR,
+            AppEvent::FocusCategory(_) => EventNames::FOCUS_CATEGORY,
+            AppEvent::ShowNotification(_) => EventNames::SHOW_NOTIFICATION,
+        }
+    }
+}
+
+// Events received from a daemon connection are marked so they aren't sent back to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventOrigin {
+    Local,
+    Connection(u64),
+}
+
+#[derive(Debug, Clone)]
+pub struct BusEvent {
+    pub event: AppEvent,
+    pub origin: EventOrigin,
+}
+
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<BusEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn emit(&self, event: AppEvent) {
+        self.emit_from(event, EventOrigin::Local);
+    }
+
+    pub fn emit_from(&self, event: AppEvent, origin: EventOrigin) {
+        // Sending only fails when nobody is listening, which is fine
+        let _ = self.sender.send(BusEvent { event, origin });
+    }
+
+    pub fn subscribe(&self) -> EventReceiver {
+        EventReceiver {
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+pub struct EventReceiver {
+    receiver: broadcast::Receiver<BusEvent>,
+}
+
+impl EventReceiver {
+    // Returns None once the bus is gone, slow listeners skip what they missed
+    pub async fn recv(&mut self) -> Option<BusEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(RecvError::Lagged(skipped)) => {
+                    crate::log::error(&format!("Event listener skipped {} events", skipped));
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
-    app_data::{AppData, PullRequestsData},
+    app_data::{AppData, PullRequestCategory, PullRequestsData},
+    notifications::NotificationState,
     AppConfig,
 };
 use serde::{Deserialize, Serialize};
@@ -14,6 +15,9 @@ impl EventNames {
     pub const FILTER_DATA_UPDATED: &'static str = "filter-data-updated";
     pub const APP_CONFIG_UPDATED: &'static str = "app-config-updated";
     pub const APP_DATA_UPDATED: &'static str = "app-data-updated";
+    pub const NOTIFICATION_STATE_UPDATED: &'static str = "notification-state-updated";
+    pub const FOCUS_CATEGORY: &'static str = "focus-category";
+    pub const SHOW_NOTIFICATION: &'static str = "show-notification";
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +35,22 @@ pub struct AppConfigUpdatedPayload {
     pub config: AppConfig,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationStateUpdatedPayload {
+    pub state: NotificationState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusCategoryPayload {
+    pub category: PullRequestCategory,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DesktopNotificationPayload {
+    pub title: String,
+    pub body: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PollingErrorPayload {
     pub filter_id: Uuid,
@@ -0,0 +1,278 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_data::{PullRequestCategory, PullRequestItem, PullRequestsData},
+    github_service::get_repo_name,
+    notifications::NOTIFICATION_CATEGORIES,
+};
+
+const CSV_HEADER: [&str; 7] = [
+    "repository",
+    "number",
+    "title",
+    "author",
+    "category",
+    "age",
+    "url",
+];
+
+// One exported PR, the same columns in CSV and JSON
+#[derive(Debug, Clone, Serialize)]
+struct ExportRow {
+    repository: String,
+    number: String,
+    title: String,
+    author: String,
+    category: String,
+    age: String,
+    url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Markdown,
+    Json,
+}
+
+pub fn category_title(category: &PullRequestCategory) -> &'static str {
+    NOTIFICATION_CATEGORIES
+        .iter()
+        .find(|n| n.category == *category)
+        .map(|n| n.title)
+        .unwrap_or("PRs")
+}
+
+// PRs grouped in the same order as the notifications
+pub fn group_by_category(
+    pull_requests: &[PullRequestItem],
+) -> Vec<(&'static str, Vec<&PullRequestItem>)> {
+    NOTIFICATION_CATEGORIES
+        .iter()
+        .map(|n| {
+            (
+                n.title,
+                pull_requests
+                    .iter()
+                    .filter(|pr| pr.category == n.category)
+                    .collect::<Vec<&PullRequestItem>>(),
+            )
+        })
+        .filter(|(_, pull_requests)| !pull_requests.is_empty())
+        .collect()
+}
+
+// Muted PRs are left out, the same as in the PR list and the HTTP API counts
+pub fn render(
+    pull_requests_data: &PullRequestsData,
+    format: &ExportFormat,
+    now: &DateTime<Utc>,
+) -> Result<String, String> {
+    let pull_requests: Vec<PullRequestItem> = pull_requests_data
+        .pull_requests
+        .iter()
+        .filter(|pr| !pr.muted)
+        .cloned()
+        .collect();
+
+    match format {
+        ExportFormat::Csv => Ok(render_csv(&pull_requests, now)),
+        ExportFormat::Markdown => Ok(render_markdown_checklist(&pull_requests, now)),
+        ExportFormat::Json => serde_json::to_string_pretty(&export_rows(&pull_requests, now))
+            .map_err(|e| e.to_string()),
+    }
+}
+
+fn export_rows(pull_requests: &[PullRequestItem], now: &DateTime<Utc>) -> Vec<ExportRow> {
+    group_by_category(pull_requests)
+        .into_iter()
+        .flat_map(|(_, group)| group)
+        .map(|pr| ExportRow {
+            repository: get_repo_name(&pr.repository_url),
+            number: pull_request_number(&pr.html_url),
+            title: pr.title.clone(),
+            author: pr.login.clone(),
+            category: category_title(&pr.category).to_string(),
+            age: format_age(&pr.created_at, now),
+            url: pr.html_url.clone(),
+        })
+        .collect()
+}
+
+fn render_csv(pull_requests: &[PullRequestItem], now: &DateTime<Utc>) -> String {
+    let mut lines = vec![CSV_HEADER.join(",")];
+    for row in export_rows(pull_requests, now) {
+        let cells = [
+            row.repository,
+            row.number,
+            row.title,
+            row.author,
+            row.category,
+            row.age,
+            row.url,
+        ];
+        lines.push(
+            cells
+                .iter()
+                .map(|cell| escape_csv(cell))
+                .collect::<Vec<String>>()
+                .join(","),
+        );
+    }
+    lines.join("\n") + "\n"
+}
+
+// A checklist per category, ready to paste into a standup or retro doc
+fn render_markdown_checklist(pull_requests: &[PullRequestItem], now: &DateTime<Utc>) -> String {
+    if pull_requests.is_empty() {
+        return "No open PRs\n".to_string();
+    }
+
+    group_by_category(pull_requests)
+        .iter()
+        .map(|(title, group)| {
+            let lines: Vec<String> = group
+                .iter()
+                .map(|pr| {
+                    format!(
+                        "- [ ] [{} #{}]({}) {} by @{} ({})",
+                        get_repo_name(&pr.repository_url),
+                        pull_request_number(&pr.html_url),
+                        pr.html_url,
+                        pr.title.replace('[', "\\[").replace(']', "\\]"),
+                        pr.login,
+                        format_age(&pr.created_at, now)
+                    )
+                })
+                .collect();
+            format!("## {} ({})\n\n{}\n", title, group.len(), lines.join("\n"))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn pull_request_number(html_url: &str) -> String {
+    html_url.rsplit('/').next().unwrap_or_default().to_string()
+}
+
+// Days for anything older than a day, hours otherwise
+fn format_age(created_at: &str, now: &DateTime<Utc>) -> String {
+    let Ok(created_at) = DateTime::parse_from_rfc3339(created_at) else {
+        return String::new();
+    };
+    let age = now.signed_duration_since(created_at);
+    if age.num_days() > 0 {
+        format!("{}d", age.num_days())
+    } else {
+        format!("{}h", age.num_hours().max(0))
+    }
+}
+
+// Cells a spreadsheet would run as a formula get a leading quote, e.g. a PR titled "=HYPERLINK(...)"
+fn escape_csv(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::pull_request;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-01-10T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn escapes_csv_cells() {
+        let cases = [
+            ("Fix login", "Fix login"),
+            (
+                "=HYPERLINK(\"http://evil\")",
+                "\"'=HYPERLINK(\"\"http://evil\"\")\"",
+            ),
+            ("+1 for this", "'+1 for this"),
+            ("-rf", "'-rf"),
+            ("@alice", "'@alice"),
+            ("Fix a-b", "Fix a-b"),
+            ("Login, logout", "\"Login, logout\""),
+            ("Say \"hi\"", "\"Say \"\"hi\"\"\""),
+            ("Two\nlines", "\"Two\nlines\""),
+            ("", ""),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(escape_csv(value), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn formats_age_in_days_or_hours() {
+        let cases = [
+            ("2024-01-10T11:30:00Z", "0h"),
+            ("2024-01-10T09:00:00Z", "3h"),
+            ("2024-01-09T12:00:01Z", "23h"),
+            ("2024-01-09T12:00:00Z", "1d"),
+            ("2023-12-31T12:00:00Z", "10d"),
+            ("2024-01-11T12:00:00Z", "0h"),
+            ("yesterday", ""),
+        ];
+        for (created_at, expected) in cases {
+            assert_eq!(format_age(created_at, &now()), expected, "{}", created_at);
+        }
+    }
+
+    #[test]
+    fn renders_markdown_checklist_per_category() {
+        let muted = PullRequestItem {
+            muted: true,
+            ..pull_request(3, "Muted", "ReviewRequested")
+        };
+        let data = PullRequestsData {
+            last_updated: 0,
+            pull_requests: vec![
+                pull_request(1, "Fix [WIP] login", "ReviewRequested"),
+                pull_request(2, "Bump deps", "Rereview"),
+                muted,
+                pull_request(4, "Add export", "ReviewRequested"),
+            ],
+        };
+        assert_eq!(
+            render(&data, &ExportFormat::Markdown, &now()).unwrap(),
+            "## PRs to re-review (1)\n\
+             \n\
+             - [ ] [octo/app #2](https://github.com/octo/app/pull/2) Bump deps by @alice (9d)\n\
+             \n\
+             ## PRs to review (2)\n\
+             \n\
+             - [ ] [octo/app #1](https://github.com/octo/app/pull/1) Fix \\[WIP\\] login by @alice (9d)\n\
+             - [ ] [octo/app #4](https://github.com/octo/app/pull/4) Add export by @alice (9d)\n"
+        );
+    }
+
+    #[test]
+    fn renders_empty_markdown_checklist() {
+        let data = PullRequestsData {
+            last_updated: 0,
+            pull_requests: vec![PullRequestItem {
+                muted: true,
+                ..pull_request(1, "Muted", "ReviewRequested")
+            }],
+        };
+        assert_eq!(
+            render(&data, &ExportFormat::Markdown, &now()).unwrap(),
+            "No open PRs\n"
+        );
+    }
+}
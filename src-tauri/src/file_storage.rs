@@ -1,11 +1,11 @@
 use serde::Deserialize;
-use tauri::{Listener, Manager};
 
 use crate::{
     app_data::{AppConfig, AppData},
     app_data_v1::{AppConfigV1, AppDataV1},
-    app_data_v2::{convert_config_to_v2, convert_data_to_v2},
-    event_names::{AppConfigUpdatedPayload, AppDataUpdatedPayload, EventNames},
+    app_data_v2::{convert_config_to_v2, convert_data_to_v2, empty_config, SmtpPasswordSourceV2},
+    event_bus::AppEvent,
+    notifications::NotificationState,
     AppState, PullRequestsData,
 };
 use std::path::PathBuf;
@@ -53,7 +53,43 @@ fn get_data_path() -> PathBuf {
     config_dir.join("data.json")
 }
 
-async fn save_data(data: AppData) {
+fn get_notification_state_path() -> PathBuf {
+    let config_dir = dirs::home_dir()
+        .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))
+        .unwrap()
+        .join(FOLDER_NAME);
+
+    config_dir.join("notification_state.json")
+}
+
+fn get_smtp_password_path() -> PathBuf {
+    let config_dir = dirs::home_dir()
+        .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))
+        .unwrap()
+        .join(FOLDER_NAME);
+
+    config_dir.join("smtp_password")
+}
+
+pub fn get_daemon_socket_path() -> PathBuf {
+    let config_dir = dirs::home_dir()
+        .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))
+        .unwrap()
+        .join(FOLDER_NAME);
+
+    config_dir.join("daemon.sock")
+}
+
+pub fn get_rpc_socket_path() -> PathBuf {
+    let config_dir = dirs::home_dir()
+        .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))
+        .unwrap()
+        .join(FOLDER_NAME);
+
+    config_dir.join("rpc.sock")
+}
+
+pub async fn save_data(data: AppData) {
     let data_path = get_data_path();
     if !data_path.exists() {
         let parent_dir = data_path.parent().unwrap();
@@ -73,16 +109,68 @@ async fn save_config(config: AppConfig) {
     std::fs::write(config_path, serde_json::to_string(&config).unwrap()).unwrap();
 }
 
+async fn save_notification_state(notification_state: NotificationState) {
+    let notification_state_path = get_notification_state_path();
+    if !notification_state_path.exists() {
+        let parent_dir = notification_state_path.parent().unwrap();
+        std::fs::create_dir_all(parent_dir).unwrap();
+    }
+
+    std::fs::write(
+        notification_state_path,
+        serde_json::to_string(&notification_state).unwrap(),
+    )
+    .unwrap();
+}
+
+// Only the current user can read the file
+fn write_private_file(path: &PathBuf, contents: &str) -> std::io::Result<()> {
+    let parent_dir = path.parent().unwrap();
+    std::fs::create_dir_all(parent_dir)?;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    std::io::Write::write_all(&mut options.open(path)?, contents.as_bytes())
+}
+
+// Older builds kept the SMTP password in config.json, returns whether it was moved out
+fn move_smtp_password_to_file(config: &mut AppConfig) -> bool {
+    let Some(password) = config.email_digest.password.take() else {
+        return false;
+    };
+    let path = get_smtp_password_path();
+    match write_private_file(&path, &password) {
+        Ok(()) => {
+            config.email_digest.password_source = Some(SmtpPasswordSourceV2::File {
+                path: path.to_string_lossy().to_string(),
+            });
+        }
+        Err(e) => crate::log::error(&format!(
+            "Failed to move the SMTP password to {}, it has to be set up again: {}",
+            path.display(),
+            e
+        )),
+    }
+    true
+}
+
+pub async fn load_notification_state() -> Result<NotificationState, String> {
+    let notification_state_path = get_notification_state_path();
+    if !notification_state_path.exists() {
+        return Ok(NotificationState::default());
+    }
+
+    let content = std::fs::read_to_string(notification_state_path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
 pub async fn load_config() -> Result<AppConfig, String> {
     let config_path = get_config_path();
     if !config_path.exists() {
         crate::log::error("Config path does not exist");
-        return Ok(AppConfig {
-            version: 2,
-            github_token: None,
-            username: None,
-            repo_config: Vec::new(),
-        });
+        return Ok(empty_config());
     }
 
     let content = std::fs::read_to_string(config_path).map_err(|e| e.to_string())?;
@@ -97,7 +185,10 @@ pub async fn load_config() -> Result<AppConfig, String> {
         save_config(config_v2.clone()).await;
         return Ok(config_v2);
     } else if version_only.version == 2 {
-        let config_v2: AppConfig = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        let mut config_v2: AppConfig = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        if move_smtp_password_to_file(&mut config_v2) {
+            save_config(config_v2.clone()).await;
+        }
         return Ok(config_v2);
     } else {
         return Err("Unsupported config version".to_string());
@@ -133,30 +224,33 @@ pub async fn load_data() -> Result<AppData, String> {
     }
 }
 
-pub async fn load_state(app_handle: tauri::AppHandle) {
+pub async fn load_state(state: AppState) {
     let config = load_config().await.expect("Failed to load config");
     let data = load_data().await.expect("Failed to load data");
+    let notification_state = load_notification_state().await.unwrap_or_else(|e| {
+        crate::log::error(&format!("Failed to load notification state: {}", e));
+        NotificationState::default()
+    });
     {
-        let state = app_handle.state::<AppState>();
         *state.data.lock().await = data;
+        *state.team_policy.lock().await = crate::team_policy::load_configured_team_policy(&config);
         *state.config.lock().await = config;
+        *state.notification_state.lock().await = notification_state;
     }
 }
 
-pub fn init_listeners(app_handle: tauri::AppHandle) {
-    app_handle.listen(EventNames::APP_DATA_UPDATED, |event| {
-        let payload: AppDataUpdatedPayload = serde_json::from_str(event.payload()).unwrap();
-
-        tauri::async_runtime::spawn(async move {
-            save_data(payload.data).await;
-        });
-    });
-
-    app_handle.listen(EventNames::APP_CONFIG_UPDATED, |event| {
-        let payload: AppConfigUpdatedPayload = serde_json::from_str(event.payload()).unwrap();
-
-        tauri::async_runtime::spawn(async move {
-            save_config(payload.config).await;
-        });
-    });
+pub fn init_listeners(state: &AppState) -> tokio::task::JoinHandle<()> {
+    let mut events = state.events.subscribe();
+    tokio::spawn(async move {
+        while let Some(bus_event) = events.recv().await {
+            match bus_event.event {
+                AppEvent::AppDataUpdated(payload) => save_data(payload.data).await,
+                AppEvent::AppConfigUpdated(payload) => save_config(payload.config).await,
+                AppEvent::NotificationStateUpdated(payload) => {
+                    save_notification_state(payload.state).await
+                }
+                _ => {}
+            }
+        }
+    })
 }
@@ -1,20 +1,90 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
 use octocrab::{
-    models::{issues::Issue, pulls::Review, Author, SimpleUser},
+    models::{
+        issues::Issue,
+        pulls::{PullRequest, Review},
+        Author, SimpleUser,
+    },
     Octocrab, Page,
 };
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    code_owners::{owner_groups, parse_code_owners, CodeOwnersRule, CODE_OWNERS_PATHS},
+    repo_rules::TeamMembers,
+};
+
+// Branch protection and team members rarely change, so they're only looked up once an hour
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+type BranchRequirementsCache = HashMap<(String, String), (BranchRequirements, Instant)>;
+
+static BRANCH_REQUIREMENTS_CACHE: LazyLock<Mutex<BranchRequirementsCache>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Keyed by repository and branch, None when the repository has no CODEOWNERS file
+type CodeOwnersCache = HashMap<(String, String), (Option<Vec<CodeOwnersRule>>, Instant)>;
+
+static CODE_OWNERS_CACHE: LazyLock<Mutex<CodeOwnersCache>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Keyed by `org/team`
+type TeamMembersCache = HashMap<String, (Vec<String>, Instant)>;
+
+static TEAM_MEMBERS_CACHE: LazyLock<Mutex<TeamMembersCache>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Keyed by repository, PR number and head SHA, so entries only change with a push
+type PullRequestListCache = HashMap<(String, u64, String), (Vec<String>, Instant)>;
+
+static CHANGED_FILES_CACHE: LazyLock<Mutex<PullRequestListCache>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static COMMITS_CACHE: LazyLock<Mutex<PullRequestListCache>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// What GitHub enforces before a PR can be merged into a branch, None when it doesn't tell us
+#[derive(Debug, Clone, Copy, Default)]
+struct BranchRequirements {
+    required_approvals: Option<usize>,
+    dismiss_stale_reviews: Option<bool>,
+}
 
 pub struct GithubClient {
     client: Octocrab,
+    // Requests left per rate limit resource, e.g. `core` or `search`, as of the last response
+    rate_limits: Mutex<BTreeMap<String, usize>>,
 }
 
+#[derive(Deserialize)]
+struct SearchResponse {
+    items: Vec<Issue>,
+}
+
+#[derive(Clone)]
 pub struct GithubPRWithReviews {
     pub pr: Issue,
     pub reviews: Vec<Review>,
     pub reviewers: GithubPRReviewResponse,
+    // None when the PR itself couldn't be fetched, the search only returns it as an issue
+    pub pull_request: Option<PullRequest>,
+    // Approvals GitHub requires to merge into the base branch, if it tells us
+    pub required_approvals: Option<usize>,
+    // Whether GitHub dismisses approvals when new commits are pushed, if it tells us
+    pub dismiss_stale_reviews: Option<bool>,
+    // Sets of CODEOWNERS owners covering the changed files
+    pub code_owners: Vec<Vec<String>>,
+    // SHAs of the PR's commits, oldest first. Only listed when the user's latest review is
+    // of an older commit than the head.
+    pub commits: Vec<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct GithubPRReviewResponse {
     pub users: Vec<SimpleUser>,
     //pub teams: Vec<RequestedTeam>,
@@ -26,6 +96,7 @@ impl GithubClient {
             client: octocrab::instance()
                 .user_access_token(github_token)
                 .unwrap(),
+            rate_limits: Mutex::new(BTreeMap::new()),
         };
     }
 
@@ -41,23 +112,90 @@ impl GithubClient {
         }
     }
 
+    pub fn rate_limits(&self) -> BTreeMap<String, usize> {
+        self.rate_limits.lock().unwrap().clone()
+    }
+
+    // Needs the read:org scope. Teams that can't be read are left out and tried again on the
+    // next poll.
+    pub async fn get_team_members(&self, teams: &[String]) -> TeamMembers {
+        let mut team_members = TeamMembers::new();
+        for team in teams {
+            let cached = TEAM_MEMBERS_CACHE
+                .lock()
+                .unwrap()
+                .get(team)
+                .filter(|(_, fetched_at)| fetched_at.elapsed() < CACHE_TTL)
+                .map(|(members, _)| members.clone());
+            let members = match cached {
+                Some(members) => members,
+                None => match self.fetch_team_members(team).await {
+                    Ok(members) => {
+                        TEAM_MEMBERS_CACHE
+                            .lock()
+                            .unwrap()
+                            .insert(team.clone(), (members.clone(), Instant::now()));
+                        members
+                    }
+                    Err(e) => {
+                        crate::log::error(&format!(
+                            "Error listing members of {}, continuing...: {}",
+                            team, e
+                        ));
+                        continue;
+                    }
+                },
+            };
+            team_members.insert(team.clone(), members);
+        }
+        team_members
+    }
+
+    async fn fetch_team_members(&self, team: &str) -> Result<Vec<String>, String> {
+        let Some((org, slug)) = team.split_once('/') else {
+            return Err("Team has to be written as org/team".to_string());
+        };
+        let first_page = self
+            .client
+            .teams(org)
+            .members(slug)
+            .per_page(100)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let members = self
+            .client
+            .all_pages(first_page)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(members.into_iter().map(|member| member.login).collect())
+    }
+
     pub async fn search_pull_requests(
         &self,
         query: String,
+        username: &str,
     ) -> Result<Vec<GithubPRWithReviews>, String> {
         crate::log::info("Fetching PRs");
 
         let per_page = if cfg!(dev) { 50 } else { 50 };
 
-        let github_response = self
-            .client
-            .search()
-            .issues_and_pull_requests(&query)
-            .sort("updated")
-            .order("desc")
-            .per_page(per_page)
-            .send()
-            .await;
+        let search_url = reqwest::Url::parse_with_params(
+            "https://api.github.com/search/issues",
+            [
+                ("q", query),
+                ("sort", "updated".to_string()),
+                ("order", "desc".to_string()),
+                ("per_page", per_page.to_string()),
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        let github_response = get_recording_rate_limit::<SearchResponse>(
+            &self.client,
+            &self.rate_limits,
+            search_url.as_str(),
+        )
+        .await;
 
         let ok_response = match github_response {
             Ok(response) => response,
@@ -74,18 +212,23 @@ impl GithubClient {
             let (owner, repo) = get_owner_and_repo(&repository_url);
             let pr_number = issue.number;
             let client = &self.client;
+            let rate_limits = &self.rate_limits;
 
             async move {
                 let reviewers_url =
                     format!("/repos/{owner}/{repo}/pulls/{pr_number}/requested_reviewers");
-                let reviewers_future = client.get(&reviewers_url, None::<&()>);
+                let reviewers_future = get_recording_rate_limit::<GithubPRReviewResponse>(
+                    client,
+                    rate_limits,
+                    &reviewers_url,
+                );
 
-                let owner = owner.to_string();
-                let repo = repo.to_string();
-                let pulls = client.pulls(owner, repo);
+                let pulls = client.pulls(owner.clone(), repo.clone());
                 let reviews_future = pulls.list_reviews(pr_number).send();
+                let pull_request_future = pulls.get(pr_number);
 
-                let (reviewers, reviews) = tokio::join!(reviewers_future, reviews_future);
+                let (reviewers, reviews, pull_request) =
+                    tokio::join!(reviewers_future, reviews_future, pull_request_future);
 
                 let reviewers = reviewers.unwrap_or_else(|e| {
                     crate::log::error(&format!("Error listing reviewers, continuing...: {}", e));
@@ -95,11 +238,55 @@ impl GithubClient {
                     crate::log::error(&format!("Error listing reviews, continuing...: {}", e));
                     Page::default()
                 });
+                let pull_request = pull_request
+                    .map_err(|e| {
+                        crate::log::error(&format!("Error getting PR, continuing...: {}", e));
+                    })
+                    .ok();
+
+                let branch_requirements = match &pull_request {
+                    Some(pull_request) => {
+                        get_branch_requirements(client, &owner, &repo, &pull_request.base.ref_field)
+                            .await
+                    }
+                    None => BranchRequirements::default(),
+                };
+                let code_owners = match &pull_request {
+                    Some(pull_request) => {
+                        get_code_owner_groups(
+                            client,
+                            &owner,
+                            &repo,
+                            &pull_request.base.ref_field,
+                            pr_number,
+                            &pull_request.head.sha,
+                        )
+                        .await
+                    }
+                    None => Vec::new(),
+                };
+                let commits = match &pull_request {
+                    Some(pull_request)
+                        if reviewed_older_commit(
+                            &reviews.items,
+                            username,
+                            &pull_request.head.sha,
+                        ) =>
+                    {
+                        get_commits(client, &owner, &repo, pr_number, &pull_request.head.sha).await
+                    }
+                    _ => Vec::new(),
+                };
 
                 GithubPRWithReviews {
                     pr: issue.clone(),
                     reviews: reviews.items,
                     reviewers,
+                    pull_request,
+                    required_approvals: branch_requirements.required_approvals,
+                    dismiss_stale_reviews: branch_requirements.dismiss_stale_reviews,
+                    code_owners,
+                    commits,
                 }
             }
         });
@@ -112,8 +299,332 @@ impl GithubClient {
     }
 }
 
+// Like Octocrab::get, but also keeps the requests left that GitHub reports with every
+// response, so the rate limit doesn't have to be asked for separately
+async fn get_recording_rate_limit<R: DeserializeOwned>(
+    client: &Octocrab,
+    rate_limits: &Mutex<BTreeMap<String, usize>>,
+    url: &str,
+) -> Result<R, String> {
+    let response = client._get(url).await.map_err(|e| e.to_string())?;
+    let response = octocrab::map_github_error(response)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    if let (Some(resource), Some(remaining)) = (
+        header("x-ratelimit-resource"),
+        header("x-ratelimit-remaining").and_then(|value| value.parse::<usize>().ok()),
+    ) {
+        rate_limits.lock().unwrap().insert(resource, remaining);
+    }
+
+    let body = client
+        .body_to_string(response)
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| e.to_string())
+}
+
+// Cached per repository and branch, also when GitHub won't tell us. Failures aren't cached,
+// the last known requirements are used until GitHub answers again.
+async fn get_branch_requirements(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+) -> BranchRequirements {
+    let key = (format!("{}/{}", owner, repo), branch.to_string());
+    let cached = BRANCH_REQUIREMENTS_CACHE.lock().unwrap().get(&key).cloned();
+    if let Some((requirements, fetched_at)) = cached {
+        if fetched_at.elapsed() < CACHE_TTL {
+            return requirements;
+        }
+    }
+
+    match fetch_branch_requirements(client, owner, repo, branch).await {
+        Ok(requirements) => {
+            BRANCH_REQUIREMENTS_CACHE
+                .lock()
+                .unwrap()
+                .insert(key, (requirements, Instant::now()));
+            requirements
+        }
+        Err(e) => {
+            crate::log::error(&format!(
+                "Error reading branch requirements of {}/{} {}, continuing...: {}",
+                owner, repo, branch, e
+            ));
+            cached
+                .map(|(requirements, _)| requirements)
+                .unwrap_or_default()
+        }
+    }
+}
+
+// Changed files are only listed for repositories that have a CODEOWNERS file
+async fn get_code_owner_groups(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    pr_number: u64,
+    head_sha: &str,
+) -> Vec<Vec<String>> {
+    let Some(rules) = get_code_owners(client, owner, repo, branch).await else {
+        return Vec::new();
+    };
+
+    let key = (
+        format!("{}/{}", owner, repo),
+        pr_number,
+        head_sha.to_string(),
+    );
+    let changed_files = cached_pull_request_list(&CHANGED_FILES_CACHE, key, async {
+        let first_page = client.pulls(owner, repo).list_files(pr_number).await?;
+        let changed_files = client.all_pages(first_page).await?;
+        Ok(changed_files
+            .into_iter()
+            .map(|file| file.filename)
+            .collect())
+    })
+    .await;
+    match changed_files {
+        Ok(changed_files) => owner_groups(&rules, &changed_files),
+        Err(e) => {
+            crate::log::error(&format!(
+                "Error listing changed files, continuing...: {}",
+                e
+            ));
+            Vec::new()
+        }
+    }
+}
+
+// Whether the user's latest review is of another commit than the head
+fn reviewed_older_commit(reviews: &[Review], username: &str, head_sha: &str) -> bool {
+    reviews
+        .iter()
+        .filter(|review| {
+            review
+                .user
+                .as_ref()
+                .is_some_and(|user| user.login == username)
+        })
+        .max_by_key(|review| review.submitted_at)
+        .and_then(|review| review.commit_id.as_deref())
+        .is_some_and(|commit_id| commit_id != head_sha)
+}
+
+async fn get_commits(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    pr_number: u64,
+    head_sha: &str,
+) -> Vec<String> {
+    let key = (
+        format!("{}/{}", owner, repo),
+        pr_number,
+        head_sha.to_string(),
+    );
+    let commits = cached_pull_request_list(&COMMITS_CACHE, key, async {
+        let first_page = client
+            .pulls(owner, repo)
+            .pr_commits(pr_number)
+            .per_page(100)
+            .send()
+            .await?;
+        let commits = client.all_pages(first_page).await?;
+        Ok(commits.into_iter().map(|commit| commit.sha).collect())
+    })
+    .await;
+    commits.unwrap_or_else(|e| {
+        crate::log::error(&format!("Error listing commits, continuing...: {}", e));
+        Vec::new()
+    })
+}
+
+// Failures aren't cached, and entries of older heads are dropped once they expire
+async fn cached_pull_request_list(
+    cache: &Mutex<PullRequestListCache>,
+    key: (String, u64, String),
+    fetch: impl std::future::Future<Output = octocrab::Result<Vec<String>>>,
+) -> octocrab::Result<Vec<String>> {
+    if let Some((list, _)) = cache.lock().unwrap().get(&key) {
+        return Ok(list.clone());
+    }
+
+    let list = fetch.await?;
+    let mut cache = cache.lock().unwrap();
+    cache.retain(|_, (_, fetched_at)| fetched_at.elapsed() < CACHE_TTL);
+    cache.insert(key, (list.clone(), Instant::now()));
+    Ok(list)
+}
+
+// Read at the base branch, that's the version GitHub enforces. Failed lookups aren't
+// cached, the last file read is used until GitHub answers again.
+async fn get_code_owners(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+) -> Option<Vec<CodeOwnersRule>> {
+    let key = (format!("{}/{}", owner, repo), branch.to_string());
+    let cached = CODE_OWNERS_CACHE.lock().unwrap().get(&key).cloned();
+    if let Some((rules, fetched_at)) = &cached {
+        if fetched_at.elapsed() < CACHE_TTL {
+            return rules.clone();
+        }
+    }
+
+    match fetch_code_owners(client, owner, repo, branch).await {
+        Ok(rules) => {
+            CODE_OWNERS_CACHE
+                .lock()
+                .unwrap()
+                .insert(key, (rules.clone(), Instant::now()));
+            rules
+        }
+        Err(e) => {
+            crate::log::error(&format!(
+                "Error reading CODEOWNERS of {}/{}, continuing...: {}",
+                owner, repo, e
+            ));
+            cached.and_then(|(rules, _)| rules)
+        }
+    }
+}
+
+// Ok(None) only when GitHub says none of the paths exist
+async fn fetch_code_owners(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+) -> Result<Option<Vec<CodeOwnersRule>>, octocrab::Error> {
+    for path in CODE_OWNERS_PATHS {
+        let content = client
+            .repos(owner, repo)
+            .get_content()
+            .path(path)
+            .r#ref(branch)
+            .send()
+            .await;
+        match content {
+            Ok(content) => {
+                if let Some(contents) = content
+                    .items
+                    .first()
+                    .and_then(|item| item.decoded_content())
+                {
+                    return Ok(Some(parse_code_owners(&contents)));
+                }
+            }
+            Err(octocrab::Error::GitHub { source, .. }) if source.status_code.as_u16() == 404 => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
+
+// Rulesets can be read with any token that can read the repository, classic branch
+// protection only with admin access. When both apply, the stricter one counts.
+async fn fetch_branch_requirements(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+) -> Result<BranchRequirements, octocrab::Error> {
+    let rules_url = format!("/repos/{owner}/{repo}/rules/branches/{branch}");
+    let from_rulesets = match client
+        .get::<Vec<serde_json::Value>, _, _>(&rules_url, None::<&()>)
+        .await
+    {
+        Ok(rules) => {
+            let pull_request_rules: Vec<&serde_json::Value> = rules
+                .iter()
+                .filter(|rule| rule["type"] == "pull_request")
+                .collect();
+            BranchRequirements {
+                required_approvals: pull_request_rules
+                    .iter()
+                    .filter_map(|rule| {
+                        rule["parameters"]["required_approving_review_count"].as_u64()
+                    })
+                    .max()
+                    .map(|count| count as usize),
+                dismiss_stale_reviews: pull_request_rules
+                    .iter()
+                    .filter_map(|rule| {
+                        rule["parameters"]["dismiss_stale_reviews_on_push"].as_bool()
+                    })
+                    .reduce(|a, b| a || b),
+            }
+        }
+        Err(e) if is_refusal(&e) => {
+            crate::log::info(&format!(
+                "Could not read rulesets of {}/{} {}: {}",
+                owner, repo, branch, e
+            ));
+            BranchRequirements::default()
+        }
+        Err(e) => return Err(e),
+    };
+
+    let protection_url =
+        format!("/repos/{owner}/{repo}/branches/{branch}/protection/required_pull_request_reviews");
+    let from_protection = match client
+        .get::<serde_json::Value, _, _>(&protection_url, None::<&()>)
+        .await
+    {
+        Ok(reviews) => BranchRequirements {
+            required_approvals: reviews["required_approving_review_count"]
+                .as_u64()
+                .map(|count| count as usize),
+            dismiss_stale_reviews: reviews["dismiss_stale_reviews"].as_bool(),
+        },
+        // Also what unprotected branches and tokens without admin scope get
+        Err(e) if is_refusal(&e) => BranchRequirements::default(),
+        Err(e) => return Err(e),
+    };
+
+    Ok(BranchRequirements {
+        required_approvals: from_rulesets
+            .required_approvals
+            .max(from_protection.required_approvals),
+        dismiss_stale_reviews: from_rulesets
+            .dismiss_stale_reviews
+            .max(from_protection.dismiss_stale_reviews),
+    })
+}
+
+// GitHub answered that there's nothing there or that we may not see it, as opposed to a
+// failure worth trying again like a server error or an exceeded rate limit
+fn is_refusal(error: &octocrab::Error) -> bool {
+    match error {
+        octocrab::Error::GitHub { source, .. } => {
+            let status = source.status_code.as_u16();
+            status == 404
+                || (status == 403 && !source.message.to_lowercase().contains("rate limit"))
+        }
+        _ => false,
+    }
+}
+
 pub fn get_owner_and_repo(repository_url: &str) -> (String, String) {
     let owner = repository_url.split("/").nth(4).unwrap();
     let repo = repository_url.split("/").nth(5).unwrap();
     (owner.to_string(), repo.to_string())
 }
+
+pub fn get_repo_name(repository_url: &str) -> String {
+    let (owner, repo) = get_owner_and_repo(repository_url);
+    format!("{}/{}", owner, repo)
+}
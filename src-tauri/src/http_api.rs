@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+use axum::{
+    extract::{Request, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        StatusCode,
+    },
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Serialize;
+
+use crate::{
+    app_data::{AppData, PullRequestCategory, PullRequestItem},
+    app_data_v2::HttpApiSettingsV2,
+    event_bus::AppEvent,
+    notifications::{format_category_counts, is_paused},
+    polling::{refresh_all_filters, POLLING_INTERVAL_SECONDS},
+    quiet_hours::is_quiet_now,
+    AppState,
+};
+
+// Categories where the next step is yours
+const WAITING_ON_ME: &[PullRequestCategory] = &[
+    PullRequestCategory::ReviewRequested,
+    PullRequestCategory::Rereview,
+    PullRequestCategory::MineChangesRequested,
+    PullRequestCategory::MineApproved,
+];
+
+#[derive(Debug, Clone, Serialize)]
+struct CountsResponse {
+    counts: HashMap<PullRequestCategory, usize>,
+    waiting_on_me: usize,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    version: &'static str,
+    last_updated: u64,
+    seconds_since_update: Option<u64>,
+    notifications_paused: bool,
+    quiet_hours_active: bool,
+}
+
+// Format of waybar custom modules, also understood by i3blocks and polybar scripts
+#[derive(Debug, Clone, Serialize)]
+struct StatusBarResponse {
+    text: String,
+    tooltip: String,
+    class: &'static str,
+}
+
+// Stops the server when dropped, also when the job itself is stopped
+struct ServerTask(tokio::task::JoinHandle<()>);
+
+impl Drop for ServerTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+// Generated tokens are 32 characters, shorter ones are too easy to guess
+const MIN_TOKEN_LENGTH: usize = 16;
+
+pub fn validate_http_api_port(port: u16) -> Result<(), String> {
+    if port == 0 {
+        return Err("HTTP API port can't be 0".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_http_api_settings(http_api: &HttpApiSettingsV2) -> Result<(), String> {
+    validate_http_api_port(http_api.port)?;
+    if http_api.token.len() < MIN_TOKEN_LENGTH {
+        return Err(format!(
+            "HTTP API token must be at least {} characters",
+            MIN_TOKEN_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+// Restarts the server whenever its settings change
+pub async fn start_http_api_job(state: AppState) {
+    let mut events = state.events.subscribe();
+    let mut settings = state.config.lock().await.http_api.clone();
+    loop {
+        let server = settings
+            .enabled
+            .then(|| ServerTask(tokio::spawn(serve(settings.clone(), state.clone()))));
+
+        let new_settings = loop {
+            let Some(bus_event) = events.recv().await else {
+                return;
+            };
+            if let AppEvent::AppConfigUpdated(payload) = bus_event.event {
+                if payload.config.http_api != settings {
+                    break payload.config.http_api;
+                }
+            }
+        };
+
+        drop(server);
+        settings = new_settings;
+    }
+}
+
+async fn serve(settings: HttpApiSettingsV2, state: AppState) {
+    let listener = match tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, settings.port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            crate::log::error(&format!(
+                "Failed to start HTTP API on port {}: {}",
+                settings.port, e
+            ));
+            return;
+        }
+    };
+
+    let router = Router::new()
+        .route("/data", get(get_data))
+        .route("/counts", get(get_counts))
+        .route("/health", get(get_health))
+        .route("/status-bar", get(get_status_bar))
+        .route("/metrics", get(get_metrics))
+        .route("/refresh", post(refresh))
+        .layer(middleware::from_fn_with_state(
+            settings.token.clone(),
+            require_token,
+        ))
+        .with_state(state);
+
+    crate::log::info(&format!(
+        "HTTP API listening on 127.0.0.1:{}",
+        settings.port
+    ));
+    if let Err(e) = axum::serve(listener, router).await {
+        crate::log::error(&format!("HTTP API stopped: {}", e));
+    }
+}
+
+// Only accepts the token as a bearer token, query strings end up in logs and shell history
+async fn require_token(State(token): State<String>, request: Request, next: Next) -> Response {
+    let provided = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    let valid = provided.is_some_and(|provided| tokens_match(provided, &token));
+    if token.is_empty() || !valid {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    next.run(request).await
+}
+
+// Compares every byte so the time taken doesn't tell how much of a guess was right
+fn tokens_match(provided: &str, token: &str) -> bool {
+    provided.len() == token.len()
+        && provided
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+async fn get_data(State(state): State<AppState>) -> Json<AppData> {
+    Json(state.data.lock().await.clone())
+}
+
+async fn get_counts(State(state): State<AppState>) -> Json<CountsResponse> {
+    let pull_requests = unmuted_pull_requests(&state).await;
+    let mut counts: HashMap<PullRequestCategory, usize> = HashMap::new();
+    for pr in pull_requests.iter() {
+        *counts.entry(pr.category.clone()).or_insert(0) += 1;
+    }
+    Json(CountsResponse {
+        waiting_on_me: waiting_on_me(&pull_requests).len(),
+        total: pull_requests.len(),
+        counts,
+    })
+}
+
+async fn get_health(State(state): State<AppState>) -> Json<HealthResponse> {
+    let last_updated = state.data.lock().await.pull_requests.last_updated;
+    let config = state.config.lock().await.clone();
+    let notification_state = state.notification_state.lock().await.clone();
+    let seconds_since_update =
+        (last_updated > 0).then(|| now_seconds().saturating_sub(last_updated));
+
+    Json(HealthResponse {
+        status: if is_stale(seconds_since_update) {
+            "stale"
+        } else {
+            "ok"
+        },
+        version: env!("CARGO_PKG_VERSION"),
+        last_updated,
+        seconds_since_update,
+        notifications_paused: is_paused(&notification_state),
+        quiet_hours_active: is_quiet_now(&config.quiet_hours),
+    })
+}
+
+async fn get_status_bar(State(state): State<AppState>) -> Json<StatusBarResponse> {
+    let last_updated = state.data.lock().await.pull_requests.last_updated;
+    let pull_requests = unmuted_pull_requests(&state).await;
+    let waiting = waiting_on_me(&pull_requests);
+
+    let counted: Vec<(&PullRequestCategory, &PullRequestItem)> =
+        waiting.iter().map(|pr| (&pr.category, *pr)).collect();
+    let tooltip = if counted.is_empty() {
+        "Nothing waiting on you".to_string()
+    } else {
+        format_category_counts(&counted).join("\n")
+    };
+
+    let seconds_since_update =
+        (last_updated > 0).then(|| now_seconds().saturating_sub(last_updated));
+    let class = if is_stale(seconds_since_update) {
+        "stale"
+    } else if waiting.is_empty() {
+        "idle"
+    } else {
+        "waiting"
+    };
+
+    Json(StatusBarResponse {
+        text: waiting.len().to_string(),
+        tooltip,
+        class,
+    })
+}
+
+async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(
+            CONTENT_TYPE,
+            "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        )],
+        state.metrics.lock().await.render(),
+    )
+}
+
+async fn refresh(State(state): State<AppState>) -> StatusCode {
+    tokio::spawn(refresh_all_filters(state));
+    StatusCode::ACCEPTED
+}
+
+async fn unmuted_pull_requests(state: &AppState) -> Vec<PullRequestItem> {
+    state
+        .data
+        .lock()
+        .await
+        .pull_requests
+        .pull_requests
+        .iter()
+        .filter(|pr| !pr.muted)
+        .cloned()
+        .collect()
+}
+
+fn waiting_on_me(pull_requests: &[PullRequestItem]) -> Vec<&PullRequestItem> {
+    pull_requests
+        .iter()
+        .filter(|pr| WAITING_ON_ME.contains(&pr.category))
+        .collect()
+}
+
+// Data is stale once a few polls in a row have been missed
+fn is_stale(seconds_since_update: Option<u64>) -> bool {
+    seconds_since_update.is_none_or(|seconds| seconds > POLLING_INTERVAL_SECONDS * 3)
+}
+
+fn now_seconds() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -1,20 +1,58 @@
+// Export, import and the settings validators are only reached through the window's commands
+#![cfg_attr(not(feature = "gui"), allow(dead_code))]
+
 // Import our custom modules
 mod app_data;
 mod app_data_v1;
 mod app_data_v2;
 mod app_state;
+pub mod cli;
+mod code_owners;
+#[cfg(feature = "gui")]
+mod commands;
+#[cfg(unix)]
+pub mod daemon;
+mod digest;
+mod email_digest;
+mod event_bus;
 mod event_names;
+mod export;
 mod file_storage;
 mod github_service;
+mod http_api;
+#[cfg(target_os = "linux")]
+mod linux_notifications;
 mod log;
+mod metrics;
+mod mute_rules;
 mod notifications;
 mod polling;
 mod pr_predicates;
+mod pull_requests;
+mod quiet_hours;
+mod reminders;
+mod repo_rules;
+#[cfg(unix)]
+mod rpc;
+mod settings_file;
+#[cfg(feature = "gui")]
+mod tauri_bridge;
+mod team_policy;
+#[cfg(test)]
+mod test_support;
+#[cfg(feature = "gui")]
+mod tray;
+mod webhook;
+mod wildcard;
 pub use app_data::{AppConfig, AppData, PullRequestItem, PullRequestsData};
 pub use app_state::AppState;
 pub use file_storage::{load_config, load_data};
+#[cfg(feature = "gui")]
+use tauri::Manager;
+#[cfg(feature = "gui")]
 use tauri_plugin_autostart::MacosLauncher;
 
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let state = AppState::new().expect("Failed to initialize app state");
@@ -36,38 +74,115 @@ pub fn run() {
         .plugin(tauri_plugin_notification::init())
         .manage(state)
         .invoke_handler(tauri::generate_handler![
-            app_state::get_config,
-            app_state::get_data,
-            verify_token,
-            app_state::save_token,
-            app_state::save_repo_config,
-            polling::refresh,
-            notifications::test_notification,
+            commands::get_config,
+            commands::get_data,
+            commands::verify_token,
+            commands::save_token,
+            commands::save_repo_config,
+            commands::save_branch_rules,
+            commands::save_dismiss_stale_approvals,
+            commands::save_approvers,
+            commands::save_mute_rules,
+            commands::save_notification_settings,
+            commands::save_quiet_hours,
+            commands::save_digest_settings,
+            commands::save_webhooks,
+            commands::save_email_digest_settings,
+            commands::save_reminder_settings,
+            commands::save_http_api_settings,
+            commands::save_team_policy_path,
+            commands::save_rereview_on_new_commits,
+            commands::refresh,
+            commands::export_pull_requests,
+            commands::copy_pull_requests_as_markdown,
+            commands::export_settings,
+            commands::import_settings,
+            commands::test_notification,
+            commands::get_notification_status,
+            commands::pause_notifications,
+            commands::resume_notifications,
+            commands::snooze_pull_request,
+            commands::test_webhook,
+            commands::test_email_digest,
         ])
+        .on_window_event(|window, event| {
+            // Closing the window keeps the app running in the tray
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                api.prevent_close();
+                window.hide().unwrap_or_else(|e| {
+                    crate::log::error(&format!("Failed to hide window: {}", e));
+                });
+            }
+        })
         .setup(|app| {
             let app_handle = app.handle();
+            tray::create_tray(app_handle)?;
             tauri::async_runtime::spawn(init(app_handle.clone()));
             Ok(())
         })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+#[cfg(feature = "gui")]
 pub async fn init(app_handle: tauri::AppHandle) {
-    file_storage::init_listeners(app_handle.clone());
-    notifications::init_listeners(app_handle.clone());
-    tauri::async_runtime::spawn(file_storage::load_state(app_handle.clone()))
+    let state = app_handle.state::<AppState>().inner().clone();
+    tauri_bridge::forward_events(app_handle.clone());
+    #[cfg(target_os = "linux")]
+    tauri::async_runtime::spawn(linux_notifications::listen_for_actions(state.clone()));
+    tauri::async_runtime::spawn(file_storage::load_state(state.clone()))
         .await
         .unwrap_or_else(|e| {
             crate::log::error(&format!("Failed to load state: {}", e));
         });
-    tauri::async_runtime::spawn(polling::start_polling_job(app_handle.clone()))
-        .await
-        .unwrap_or_else(|e| {
-            crate::log::error(&format!("Failed to start polling job: {}", e));
-        });
+    tray::update_tray(&app_handle).await;
+
+    // A running daemon already polls and notifies, the app only mirrors it and hands
+    // polling back whenever the daemon is started again
+    #[cfg(unix)]
+    {
+        let mut daemon = daemon::connect().await;
+        loop {
+            if let Some(stream) = daemon {
+                crate::log::info("Attached to daemon");
+                daemon::attach(stream, state.clone()).await;
+                crate::log::info("Daemon went away, polling from the app");
+            }
+            let jobs = start_background_jobs(&state);
+            daemon = Some(daemon::wait_for_daemon().await);
+            crate::log::info("Daemon is back, leaving polling to it");
+            jobs.stop();
+        }
+    }
+    #[cfg(not(unix))]
+    start_background_jobs(&state);
+}
+
+// Everything that polls, notifies or writes files, only one process runs these at a time
+pub struct BackgroundJobs(Vec<tokio::task::JoinHandle<()>>);
+
+impl BackgroundJobs {
+    pub fn stop(self) {
+        for job in self.0 {
+            job.abort();
+        }
+    }
+}
+
+pub fn start_background_jobs(state: &AppState) -> BackgroundJobs {
+    BackgroundJobs(vec![
+        file_storage::init_listeners(state),
+        notifications::init_listeners(state),
+        tokio::spawn(notifications::start_scheduled_notifications_job(
+            state.clone(),
+        )),
+        tokio::spawn(http_api::start_http_api_job(state.clone())),
+        tokio::spawn(team_policy::start_team_policy_job(state.clone())),
+        #[cfg(unix)]
+        tokio::spawn(rpc::start_rpc_job(state.clone())),
+        tokio::spawn(polling::start_polling_job(state.clone())),
+    ])
 }
 
-#[tauri::command]
 async fn verify_token(token: String) -> Result<String, ()> {
     let client = github_service::GithubClient::new(token);
     let user = client.get_user().await;
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::sync::OnceCell;
+use zbus::{proxy, zvariant::Value, Connection};
+
+use crate::{
+    notifications::{handle_notification_action, DesktopNotification, NotificationAction},
+    AppState,
+};
+
+const APP_NAME: &str = "pr-sentinel";
+const SOUND_NAME: &str = "message-new-instant";
+const EXPIRE_TIMEOUT_MS: i32 = 5000;
+const INITIAL_RETRY_SECONDS: u64 = 1;
+const MAX_RETRY_SECONDS: u64 = 5 * 60;
+
+#[proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+}
+
+// Talks to the notification server on the session bus. Notifications sharing a replace
+// key update each other instead of stacking, and actions are remembered by notification
+// id until the server reports that they were invoked or closed.
+struct LinuxNotifier {
+    proxy: NotificationsProxy<'static>,
+    replace_ids: Mutex<HashMap<String, u32>>,
+    actions: Mutex<HashMap<u32, NotificationAction>>,
+}
+
+impl LinuxNotifier {
+    async fn new(connection: &Connection) -> Result<LinuxNotifier, String> {
+        let proxy = NotificationsProxy::new(connection)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(LinuxNotifier {
+            proxy,
+            replace_ids: Mutex::new(HashMap::new()),
+            actions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    async fn send(&self, notification: &DesktopNotification) -> Result<u32, String> {
+        let replaces_id = notification
+            .replace_key
+            .as_ref()
+            .and_then(|key| self.replace_ids.lock().unwrap().get(key).copied())
+            .unwrap_or(0);
+
+        let actions: Vec<&str> = match notification.action {
+            Some(_) => vec!["default", "Open"],
+            None => Vec::new(),
+        };
+
+        let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+        hints.insert("urgency", Value::from(notification.urgency.level()));
+        hints.insert("sound-name", Value::from(SOUND_NAME));
+
+        let id = self
+            .proxy
+            .notify(
+                APP_NAME,
+                replaces_id,
+                notification.icon,
+                &notification.title,
+                &notification.body,
+                &actions,
+                hints,
+                EXPIRE_TIMEOUT_MS,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if let Some(key) = &notification.replace_key {
+            self.replace_ids.lock().unwrap().insert(key.clone(), id);
+        }
+        if let Some(action) = &notification.action {
+            self.actions.lock().unwrap().insert(id, action.clone());
+        }
+        Ok(id)
+    }
+}
+
+static NOTIFIER: OnceCell<LinuxNotifier> = OnceCell::const_new();
+
+async fn get_notifier() -> Result<&'static LinuxNotifier, String> {
+    NOTIFIER
+        .get_or_try_init(|| async {
+            let connection = Connection::session().await.map_err(|e| e.to_string())?;
+            LinuxNotifier::new(&connection).await
+        })
+        .await
+}
+
+async fn subscribe_to_signals() -> Result<
+    (
+        &'static LinuxNotifier,
+        ActionInvokedStream,
+        NotificationClosedStream,
+    ),
+    String,
+> {
+    let notifier = get_notifier()
+        .await
+        .map_err(|e| format!("Failed to connect to notification server: {}", e))?;
+    let (invoked, closed) = tokio::join!(
+        notifier.proxy.receive_action_invoked(),
+        notifier.proxy.receive_notification_closed()
+    );
+    match (invoked, closed) {
+        (Ok(invoked), Ok(closed)) => Ok((notifier, invoked, closed)),
+        (Err(e), _) | (_, Err(e)) => {
+            Err(format!("Failed to listen for notification signals: {}", e))
+        }
+    }
+}
+
+// The session bus may not be up yet when we're started at login, so keep retrying with
+// backoff instead of giving up on notification actions for the whole session
+pub async fn listen_for_actions(state: AppState) {
+    let mut backoff = Duration::from_secs(INITIAL_RETRY_SECONDS);
+    let (notifier, mut invoked, mut closed) = loop {
+        match subscribe_to_signals().await {
+            Ok(subscription) => break subscription,
+            Err(e) => {
+                crate::log::error(&format!("{}, retrying in {:?}", e, backoff));
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(Duration::from_secs(MAX_RETRY_SECONDS));
+            }
+        }
+    };
+
+    loop {
+        tokio::select! {
+            Some(signal) = invoked.next() => {
+                let Ok(args) = signal.args() else {
+                    continue;
+                };
+                let action = notifier.actions.lock().unwrap().remove(&args.id);
+                if let Some(action) = action {
+                    handle_notification_action(&state, &action);
+                }
+            }
+            Some(signal) = closed.next() => {
+                if let Ok(args) = signal.args() {
+                    notifier.actions.lock().unwrap().remove(&args.id);
+                }
+            }
+            else => break,
+        }
+    }
+}
+
+pub async fn send_notification(notification: &DesktopNotification) -> Result<String, String> {
+    let id = get_notifier().await?.send(notification).await?;
+    Ok(format!("Successfully sent notification with id: {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::net::UnixStream, sync::Arc};
+
+    use zbus::{connection::Builder, interface, zvariant::OwnedValue, Guid};
+
+    use super::*;
+    use crate::notifications::NotificationUrgency;
+
+    #[derive(Debug, PartialEq)]
+    struct ReceivedNotification {
+        replaces_id: u32,
+        summary: String,
+        actions: Vec<String>,
+        urgency: Option<u8>,
+    }
+
+    // Stands in for the notification server on the session bus
+    struct MockNotificationServer {
+        received: Arc<Mutex<Vec<ReceivedNotification>>>,
+        last_id: u32,
+    }
+
+    #[interface(name = "org.freedesktop.Notifications")]
+    impl MockNotificationServer {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &mut self,
+            _app_name: &str,
+            replaces_id: u32,
+            _app_icon: &str,
+            summary: &str,
+            _body: &str,
+            actions: Vec<String>,
+            hints: HashMap<String, OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            self.received.lock().unwrap().push(ReceivedNotification {
+                replaces_id,
+                summary: summary.to_string(),
+                actions,
+                urgency: hints
+                    .get("urgency")
+                    .and_then(|urgency| u8::try_from(urgency).ok()),
+            });
+            if replaces_id != 0 {
+                return replaces_id;
+            }
+            self.last_id += 1;
+            self.last_id
+        }
+    }
+
+    async fn connect_to_mock_server() -> (
+        Connection,
+        Connection,
+        Arc<Mutex<Vec<ReceivedNotification>>>,
+    ) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let server = MockNotificationServer {
+            received: received.clone(),
+            last_id: 0,
+        };
+        let (server_stream, client_stream) = UnixStream::pair().unwrap();
+        let (server, client) = futures::try_join!(
+            Builder::unix_stream(server_stream)
+                .server(Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at("/org/freedesktop/Notifications", server)
+                .unwrap()
+                .build(),
+            Builder::unix_stream(client_stream).p2p().build(),
+        )
+        .unwrap();
+        (server, client, received)
+    }
+
+    fn notification(title: &str, replace_key: Option<&str>) -> DesktopNotification {
+        DesktopNotification {
+            title: title.to_string(),
+            body: "".to_string(),
+            urgency: NotificationUrgency::Critical,
+            icon: "dialog-information",
+            replace_key: replace_key.map(String::from),
+            action: Some(NotificationAction::OpenUrl {
+                url: "https://github.com".to_string(),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn replaces_notifications_sharing_a_key() {
+        let (_server, client, received) = connect_to_mock_server().await;
+        let notifier = LinuxNotifier::new(&client).await.unwrap();
+
+        let first = notifier
+            .send(&notification("First", Some("category")))
+            .await
+            .unwrap();
+        let second = notifier
+            .send(&notification("Second", Some("category")))
+            .await
+            .unwrap();
+        let other = notifier.send(&notification("Other", None)).await.unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        let received = received.lock().unwrap();
+        assert_eq!(
+            received.iter().map(|n| n.replaces_id).collect::<Vec<u32>>(),
+            vec![0, first, 0]
+        );
+        assert_eq!(received[1].summary, "Second");
+        assert_eq!(received[0].actions, vec!["default", "Open"]);
+        assert_eq!(received[0].urgency, Some(2));
+        assert!(notifier.actions.lock().unwrap().contains_key(&other));
+    }
+}
@@ -2,5 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    #[cfg(unix)]
+    if std::env::args().any(|arg| arg == "--daemon") {
+        return pr_sentinel_lib::daemon::run();
+    }
     pr_sentinel_lib::run()
 }
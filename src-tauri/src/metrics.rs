@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::{
+    app_data::{PullRequestCategory, PullRequestsData},
+    github_service::get_repo_name,
+    notifications::NOTIFICATION_CATEGORIES,
+};
+
+// Upper bounds of the poll duration histogram, polls time out after 30s
+const POLL_DURATION_BUCKETS: [f64; 7] = [0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 30.0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PollErrorKind {
+    NoToken,
+    Github,
+    Timeout,
+}
+
+impl PollErrorKind {
+    fn label(&self) -> &'static str {
+        match self {
+            PollErrorKind::NoToken => "no_token",
+            PollErrorKind::Github => "github",
+            PollErrorKind::Timeout => "timeout",
+        }
+    }
+}
+
+// Kept in memory only, counters start over with the process like Prometheus expects
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    // Unmuted PRs per category and repository, from the last successful poll. Every category
+    // of every repository seen so far is kept, at 0 when empty, so series don't disappear.
+    pull_requests: BTreeMap<(String, String), usize>,
+    poll_duration_buckets: [u64; POLL_DURATION_BUCKETS.len()],
+    poll_duration_sum: f64,
+    poll_count: u64,
+    poll_errors: BTreeMap<PollErrorKind, u64>,
+    // Requests left per rate limit resource, as reported with the last responses
+    rate_limit_remaining: BTreeMap<String, usize>,
+    last_success: Option<u64>,
+}
+
+impl Metrics {
+    pub fn record_pull_requests(&mut self, pull_requests_data: &PullRequestsData) {
+        let mut repos: BTreeSet<String> = self
+            .pull_requests
+            .keys()
+            .map(|(_, repo)| repo.clone())
+            .collect();
+        repos.extend(
+            pull_requests_data
+                .pull_requests
+                .iter()
+                .map(|pr| get_repo_name(&pr.repository_url)),
+        );
+        let mut counts: HashMap<(String, String), usize> = repos
+            .iter()
+            .flat_map(|repo| {
+                NOTIFICATION_CATEGORIES
+                    .iter()
+                    .map(|n| ((category_label(&n.category).to_string(), repo.clone()), 0))
+            })
+            .collect();
+        for pr in pull_requests_data
+            .pull_requests
+            .iter()
+            .filter(|pr| !pr.muted)
+        {
+            let key = (
+                category_label(&pr.category).to_string(),
+                get_repo_name(&pr.repository_url),
+            );
+            *counts.entry(key).or_insert(0) += 1;
+        }
+        self.pull_requests = counts.into_iter().collect();
+        self.last_success = Some(pull_requests_data.last_updated);
+    }
+
+    pub fn record_poll(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        for (count, bound) in self
+            .poll_duration_buckets
+            .iter_mut()
+            .zip(POLL_DURATION_BUCKETS.iter())
+        {
+            if seconds <= *bound {
+                *count += 1;
+            }
+        }
+        self.poll_duration_sum += seconds;
+        self.poll_count += 1;
+    }
+
+    pub fn poll_count(&self) -> u64 {
+        self.poll_count
+    }
+
+    pub fn record_poll_error(&mut self, kind: PollErrorKind) {
+        *self.poll_errors.entry(kind).or_insert(0) += 1;
+    }
+
+    pub fn record_rate_limits(&mut self, rate_limits: BTreeMap<String, usize>) {
+        self.rate_limit_remaining.extend(rate_limits);
+    }
+
+    // OpenMetrics text format, see https://openmetrics.io
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+
+        writeln!(output, "# TYPE pr_sentinel_pull_requests gauge").unwrap();
+        writeln!(
+            output,
+            "# HELP pr_sentinel_pull_requests Unmuted open PRs by category and repository."
+        )
+        .unwrap();
+        for ((category, repo), count) in self.pull_requests.iter() {
+            writeln!(
+                output,
+                "pr_sentinel_pull_requests{{category=\"{}\",repo=\"{}\"}} {}",
+                escape_label(category),
+                escape_label(repo),
+                count
+            )
+            .unwrap();
+        }
+
+        writeln!(output, "# TYPE pr_sentinel_poll_duration_seconds histogram").unwrap();
+        writeln!(output, "# UNIT pr_sentinel_poll_duration_seconds seconds").unwrap();
+        writeln!(
+            output,
+            "# HELP pr_sentinel_poll_duration_seconds Time taken to fetch PRs from GitHub."
+        )
+        .unwrap();
+        for (count, bound) in self
+            .poll_duration_buckets
+            .iter()
+            .zip(POLL_DURATION_BUCKETS.iter())
+        {
+            writeln!(
+                output,
+                "pr_sentinel_poll_duration_seconds_bucket{{le=\"{:?}\"}} {}",
+                bound, count
+            )
+            .unwrap();
+        }
+        writeln!(
+            output,
+            "pr_sentinel_poll_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            self.poll_count
+        )
+        .unwrap();
+        writeln!(
+            output,
+            "pr_sentinel_poll_duration_seconds_sum {}",
+            self.poll_duration_sum
+        )
+        .unwrap();
+        writeln!(
+            output,
+            "pr_sentinel_poll_duration_seconds_count {}",
+            self.poll_count
+        )
+        .unwrap();
+
+        writeln!(output, "# TYPE pr_sentinel_poll_errors counter").unwrap();
+        writeln!(
+            output,
+            "# HELP pr_sentinel_poll_errors Failed polls by kind."
+        )
+        .unwrap();
+        for kind in [
+            PollErrorKind::NoToken,
+            PollErrorKind::Github,
+            PollErrorKind::Timeout,
+        ] {
+            writeln!(
+                output,
+                "pr_sentinel_poll_errors_total{{kind=\"{}\"}} {}",
+                kind.label(),
+                self.poll_errors.get(&kind).unwrap_or(&0)
+            )
+            .unwrap();
+        }
+
+        if !self.rate_limit_remaining.is_empty() {
+            writeln!(
+                output,
+                "# TYPE pr_sentinel_github_rate_limit_remaining gauge"
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "# HELP pr_sentinel_github_rate_limit_remaining Requests left in the current GitHub rate limit window."
+            )
+            .unwrap();
+            for (resource, remaining) in self.rate_limit_remaining.iter() {
+                writeln!(
+                    output,
+                    "pr_sentinel_github_rate_limit_remaining{{resource=\"{}\"}} {}",
+                    escape_label(resource),
+                    remaining
+                )
+                .unwrap();
+            }
+        }
+
+        if let Some(last_success) = self.last_success {
+            writeln!(
+                output,
+                "# TYPE pr_sentinel_last_success_timestamp_seconds gauge"
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "# UNIT pr_sentinel_last_success_timestamp_seconds seconds"
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "# HELP pr_sentinel_last_success_timestamp_seconds Time of the last successful poll."
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "pr_sentinel_last_success_timestamp_seconds {}",
+                last_success
+            )
+            .unwrap();
+        }
+
+        output.push_str("# EOF\n");
+        output
+    }
+}
+
+fn category_label(category: &PullRequestCategory) -> &'static str {
+    match category {
+        PullRequestCategory::MineApproved => "mine_approved",
+        PullRequestCategory::MineChangesRequested => "mine_changes_requested",
+        PullRequestCategory::MinePending => "mine_pending",
+        PullRequestCategory::ReviewRequested => "review_requested",
+        PullRequestCategory::Rereview => "rereview",
+        PullRequestCategory::ReviewMissing => "review_missing",
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,115 @@
+use crate::{
+    app_data_v2::MuteRuleV2,
+    github_service::{get_repo_name, GithubPRWithReviews},
+    wildcard::matches_wildcard,
+    AppConfig,
+};
+
+pub fn is_muted(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> bool {
+    config
+        .mute_rules
+        .iter()
+        .any(|rule| rule_matches(rule, pr_with_reviews))
+}
+
+fn rule_matches(rule: &MuteRuleV2, pr_with_reviews: &GithubPRWithReviews) -> bool {
+    let pr = &pr_with_reviews.pr;
+    let labels: Vec<&str> = pr.labels.iter().map(|label| label.name.as_str()).collect();
+
+    criteria_match(
+        rule,
+        &get_repo_name(pr.repository_url.as_str()),
+        &pr.user.login,
+        &labels,
+    )
+}
+
+fn criteria_match(rule: &MuteRuleV2, repo_name: &str, author: &str, labels: &[&str]) -> bool {
+    // A rule without any criteria would mute everything, treat it as inactive instead
+    if rule.repo_name.is_none() && rule.author.is_none() && rule.label.is_none() {
+        return false;
+    }
+
+    let repo_matches = rule
+        .repo_name
+        .as_ref()
+        .is_none_or(|pattern| matches_wildcard(pattern, repo_name));
+    let author_matches = rule
+        .author
+        .as_ref()
+        .is_none_or(|pattern| matches_wildcard(pattern, author));
+    let label_matches = rule
+        .label
+        .as_ref()
+        .is_none_or(|pattern| labels.iter().any(|label| matches_wildcard(pattern, label)));
+
+    repo_matches && author_matches && label_matches
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn rule(repo_name: Option<&str>, author: Option<&str>, label: Option<&str>) -> MuteRuleV2 {
+        MuteRuleV2 {
+            id: Uuid::new_v4(),
+            repo_name: repo_name.map(String::from),
+            author: author.map(String::from),
+            label: label.map(String::from),
+        }
+    }
+
+    #[test]
+    fn every_set_criterion_has_to_match() {
+        let cases = [
+            (rule(Some("octo/app"), None, None), true),
+            (rule(Some("octo/*"), None, None), true),
+            (rule(Some("octo/web"), None, None), false),
+            (rule(None, Some("dependabot*"), None), true),
+            (rule(None, Some("alice"), None), false),
+            (rule(None, None, Some("deps")), true),
+            (rule(None, None, Some("wip")), false),
+            (rule(Some("octo/app"), Some("dependabot[bot]"), None), true),
+            (rule(Some("octo/app"), Some("alice"), None), false),
+            (rule(Some("octo/web"), Some("dependabot[bot]"), None), false),
+            (
+                rule(Some("octo/*"), Some("dependabot*"), Some("chore")),
+                true,
+            ),
+            (
+                rule(Some("octo/*"), Some("dependabot*"), Some("wip")),
+                false,
+            ),
+        ];
+        for (rule, expected) in cases {
+            assert_eq!(
+                criteria_match(&rule, "octo/app", "dependabot[bot]", &["deps", "chore"]),
+                expected,
+                "{:?}",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn rule_without_criteria_is_inactive() {
+        assert!(!criteria_match(
+            &rule(None, None, None),
+            "octo/app",
+            "alice",
+            &["deps"]
+        ));
+    }
+
+    #[test]
+    fn label_criterion_needs_a_label() {
+        assert!(!criteria_match(
+            &rule(None, None, Some("*")),
+            "octo/app",
+            "alice",
+            &[]
+        ));
+    }
+}
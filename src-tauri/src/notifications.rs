@@ -1,169 +1,792 @@
 use crate::{
     app_data::PullRequestCategory,
-    event_names::{EventNames, FilterDataUpdatedPayload},
-    PullRequestItem,
+    app_data_v2::{default_category_notification_settings, NotificationSettingsV2},
+    digest::{format_digest, is_digest_due},
+    email_digest::send_email_digest_if_due,
+    event_bus::AppEvent,
+    event_names::{
+        FilterDataUpdatedPayload, FocusCategoryPayload, NotificationStateUpdatedPayload,
+    },
+    github_service::get_repo_name,
+    quiet_hours::is_quiet_now,
+    reminders::{send_due_reminders, ReminderState},
+    webhook::WebhookChannel,
+    wildcard::matches_wildcard,
+    AppConfig, AppState, PullRequestItem,
 };
+use futures::future::{join_all, BoxFuture};
 use serde::{Deserialize, Serialize};
-use tauri::Listener;
-use tauri_plugin_notification::NotificationExt;
-
-pub fn init_listeners(app_handle: tauri::AppHandle) {
-    let my_app_handle = app_handle.clone();
-    app_handle.listen(EventNames::FILTER_DATA_UPDATED, move |event| {
-        let payload: FilterDataUpdatedPayload = serde_json::from_str(event.payload()).unwrap();
-        notify_new_pull_requests(payload, my_app_handle.clone());
-    });
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time;
+
+const SCHEDULED_NOTIFICATIONS_INTERVAL_SECONDS: u64 = 60;
+const NOTIFIED_HISTORY_RETENTION_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+// Notifications that arrive during quiet hours or while paused are held back and
+// released as a single summary once notifications are allowed again. In digest mode
+// all category changes are collected and sent together on the configured cadence.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationState {
+    pub paused_until: Option<u64>,
+    pub held_notifications: Vec<HeldNotification>,
+    pub digest_entries: Vec<DigestEntry>,
+    pub last_digest_sent: u64,
+    pub last_email_digest_sent: u64,
+    // The category each PR was last notified (or baselined) in, so restarts and data
+    // resets don't notify everything again
+    pub notified: HashMap<u64, NotifiedPullRequest>,
+    pub baseline_established: bool,
+    pub reminders: HashMap<u64, ReminderState>,
+    // PR id to the time its notifications and reminders are snoozed until
+    pub snoozed: HashMap<u64, u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotifiedPullRequest {
+    pub category: PullRequestCategory,
+    pub last_seen: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeldNotification {
+    pub category: PullRequestCategory,
+    pub pull_requests: Vec<PullRequestItem>,
+    // Notifications held before the reason was recorded were all held by a pause
+    #[serde(default)]
+    pub reason: HoldReason,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum HoldReason {
+    #[default]
+    Paused,
+    QuietHours,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestEntry {
+    pub category: PullRequestCategory,
+    pub pull_request: PullRequestItem,
+    pub changed_at: u64,
+}
+
+pub struct CategoryNotification {
+    pub category: PullRequestCategory,
+    pub title: &'static str,
+    pub urgency: NotificationUrgency,
+    pub icon: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationUrgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+impl NotificationUrgency {
+    // Urgency levels as defined by the freedesktop notification spec
+    pub fn level(&self) -> u8 {
+        match self {
+            NotificationUrgency::Low => 0,
+            NotificationUrgency::Normal => 1,
+            NotificationUrgency::Critical => 2,
+        }
+    }
+}
+
+pub struct DesktopNotification {
+    pub title: String,
+    pub body: String,
+    pub urgency: NotificationUrgency,
+    pub icon: &'static str,
+    // Notifications with the same key replace each other where supported
+    pub replace_key: Option<String>,
+    pub action: Option<NotificationAction>,
 }
 
-fn notify_new_pull_requests(payload: FilterDataUpdatedPayload, app_handle: tauri::AppHandle) {
-    let mut prs_missing_review: Vec<PullRequestItem> = Vec::new();
-    let mut prs_rereview: Vec<PullRequestItem> = Vec::new();
-    let mut prs_approved: Vec<PullRequestItem> = Vec::new();
-    let mut prs_changes_requested: Vec<PullRequestItem> = Vec::new();
+// Notifications are sent in this order, one per category with changed PRs
+pub static NOTIFICATION_CATEGORIES: &[CategoryNotification] = &[
+    CategoryNotification {
+        category: PullRequestCategory::Rereview,
+        title: "PRs to re-review",
+        urgency: NotificationUrgency::Normal,
+        icon: "view-refresh",
+    },
+    CategoryNotification {
+        category: PullRequestCategory::MineApproved,
+        title: "PRs approved",
+        urgency: NotificationUrgency::Normal,
+        icon: "emblem-default",
+    },
+    CategoryNotification {
+        category: PullRequestCategory::MineChangesRequested,
+        title: "PRs rejected",
+        urgency: NotificationUrgency::Critical,
+        icon: "dialog-warning",
+    },
+    CategoryNotification {
+        category: PullRequestCategory::ReviewMissing,
+        title: "PRs missing review",
+        urgency: NotificationUrgency::Normal,
+        icon: "dialog-information",
+    },
+    CategoryNotification {
+        category: PullRequestCategory::ReviewRequested,
+        title: "PRs to review",
+        urgency: NotificationUrgency::Normal,
+        icon: "mail-unread",
+    },
+    CategoryNotification {
+        category: PullRequestCategory::MinePending,
+        title: "PRs pending review",
+        urgency: NotificationUrgency::Low,
+        icon: "appointment-soon",
+    },
+];
 
-    let new_data = payload.new_data;
+pub fn init_listeners(state: &AppState) -> tokio::task::JoinHandle<()> {
+    let state = state.clone();
+    let mut events = state.events.subscribe();
+    tokio::spawn(async move {
+        while let Some(bus_event) = events.recv().await {
+            let AppEvent::FilterDataUpdated(payload) = bus_event.event else {
+                continue;
+            };
+            let state = state.clone();
+            tokio::spawn(async move {
+                let config = state.config.lock().await.clone();
+                notify_new_pull_requests(payload, &config, &state).await;
+            });
+        }
+    })
+}
 
-    let old_data = payload.old_data;
+// A destination for category transitions, e.g. desktop toasts or a webhook
+pub trait NotificationChannel: Send + Sync {
+    fn name(&self) -> String;
+    fn send<'a>(
+        &'a self,
+        transition: &'a CategoryTransition,
+    ) -> BoxFuture<'a, Result<String, String>>;
+}
 
-    for pr in new_data.pull_requests {
-        let old_pr = old_data
-            .pull_requests
+// PRs that moved into a category since the last poll
+pub struct CategoryTransition {
+    pub category_notification: &'static CategoryNotification,
+    pub pull_requests: Vec<PullRequestItem>,
+}
+
+// Desktop toasts honour digest mode, quiet hours and pauses, other channels are sent
+// right away since they are meant to reach you when you are away from the desktop
+struct DesktopChannel {
+    state: AppState,
+    config: AppConfig,
+}
+
+impl NotificationChannel for DesktopChannel {
+    fn name(&self) -> String {
+        "desktop".to_string()
+    }
+
+    fn send<'a>(
+        &'a self,
+        transition: &'a CategoryTransition,
+    ) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(async move {
+            if self.config.digest.enabled {
+                add_to_digest(transition.pull_requests.clone(), &self.state).await;
+                return Ok("Added to digest".to_string());
+            }
+            dispatch_pull_request_notification(transition, &self.state, &self.config).await
+        })
+    }
+}
+
+fn notification_channels(
+    config: &AppConfig,
+    state: &AppState,
+) -> Vec<Box<dyn NotificationChannel>> {
+    let mut channels: Vec<Box<dyn NotificationChannel>> = vec![Box::new(DesktopChannel {
+        state: state.clone(),
+        config: config.clone(),
+    })];
+    channels.extend(
+        config
+            .webhooks
             .iter()
-            .find(|old_pr| old_pr.id == pr.id);
-        if old_pr.map_or(true, |existing_pr| existing_pr.category != pr.category) {
-            if pr.category == PullRequestCategory::Rereview {
-                prs_rereview.push(pr);
-            } else if pr.category == PullRequestCategory::MineApproved {
-                prs_approved.push(pr);
-            } else if pr.category == PullRequestCategory::MineChangesRequested {
-                prs_changes_requested.push(pr);
-            } else if pr.category == PullRequestCategory::ReviewMissing {
-                prs_missing_review.push(pr);
+            .filter(|w| w.enabled)
+            .map(|w| Box::new(WebhookChannel::new(w.clone())) as Box<dyn NotificationChannel>),
+    );
+    channels
+}
+
+async fn notify_new_pull_requests(
+    payload: FilterDataUpdatedPayload,
+    config: &AppConfig,
+    state: &AppState,
+) {
+    let changed_prs = record_notified_pull_requests(&payload.new_data.pull_requests, state)
+        .await
+        .into_iter()
+        .filter(|pr| !pr.muted)
+        .collect::<Vec<PullRequestItem>>();
+
+    let transitions: Vec<CategoryTransition> = NOTIFICATION_CATEGORIES
+        .iter()
+        .map(|category_notification| CategoryTransition {
+            category_notification,
+            pull_requests: changed_prs
+                .iter()
+                .filter(|pr| pr.category == category_notification.category)
+                .filter(|pr| {
+                    should_notify(
+                        &config.notification_settings,
+                        &pr.category,
+                        &get_repo_name(&pr.repository_url),
+                    )
+                })
+                .cloned()
+                .collect(),
+        })
+        .filter(|t| !t.pull_requests.is_empty())
+        .collect();
+
+    if transitions.is_empty() {
+        return;
+    }
+
+    let channels = notification_channels(config, state);
+    let sends = channels.iter().map(|channel| {
+        let transitions = &transitions;
+        async move {
+            for transition in transitions.iter() {
+                match channel.send(transition).await {
+                    Ok(s) => crate::log::info(&format!(
+                        "Notification sent via {}: {}",
+                        channel.name(),
+                        s
+                    )),
+                    Err(e) => crate::log::error(&format!(
+                        "Failed to send notification via {}: {}",
+                        channel.name(),
+                        e
+                    )),
+                }
             }
         }
+    });
+    join_all(sends).await;
+}
+
+// Returns the PRs whose category differs from the one last notified, and records the
+// current categories. The first poll that finds PRs only records a baseline so it doesn't
+// flood. Failed polls never get here.
+async fn record_notified_pull_requests(
+    pull_requests: &[PullRequestItem],
+    state: &AppState,
+) -> Vec<PullRequestItem> {
+    let changed_prs = {
+        let mut notification_state = state.notification_state.lock().await;
+        let now = now_seconds();
+
+        notification_state.snoozed.retain(|_, until| *until > now);
+        let changed_prs: Vec<PullRequestItem> = pull_requests
+            .iter()
+            .filter(|pr| {
+                notification_state
+                    .notified
+                    .get(&pr.id)
+                    .is_none_or(|notified| notified.category != pr.category)
+            })
+            .filter(|pr| !is_snoozed(&notification_state, pr.id))
+            .cloned()
+            .collect();
+
+        for pr in pull_requests.iter() {
+            notification_state.notified.insert(
+                pr.id,
+                NotifiedPullRequest {
+                    category: pr.category.clone(),
+                    last_seen: now,
+                },
+            );
+        }
+        notification_state
+            .notified
+            .retain(|_, n| now.saturating_sub(n.last_seen) < NOTIFIED_HISTORY_RETENTION_SECONDS);
+
+        if notification_state.baseline_established {
+            changed_prs
+        } else if pull_requests.is_empty() {
+            // Nothing to record yet, e.g. the search index hasn't caught up after a restart
+            Vec::new()
+        } else {
+            crate::log::info(&format!(
+                "Recorded notification baseline for {} PRs",
+                pull_requests.len()
+            ));
+            notification_state.baseline_established = true;
+            Vec::new()
+        }
+    };
+    persist_notification_state(state).await;
+    changed_prs
+}
+
+async fn dispatch_pull_request_notification(
+    transition: &CategoryTransition,
+    state: &AppState,
+    config: &AppConfig,
+) -> Result<String, String> {
+    let category_notification = transition.category_notification;
+    let mut notification_state = state.notification_state.lock().await;
+    if let Some(reason) = hold_reason(config, &notification_state) {
+        notification_state
+            .held_notifications
+            .push(HeldNotification {
+                category: category_notification.category.clone(),
+                pull_requests: transition.pull_requests.clone(),
+                reason,
+            });
+        drop(notification_state);
+        persist_notification_state(state).await;
+        return Ok(format!(
+            "Holding notification: {}",
+            category_notification.title
+        ));
     }
+    drop(notification_state);
+    send_pull_request_notification(&transition.pull_requests, state, category_notification).await
+}
 
-    if prs_rereview.len() > 0 {
-        send_pull_request_notification(prs_rereview, app_handle.clone(), "PRs to re-review");
+async fn add_to_digest(pull_requests: Vec<PullRequestItem>, state: &AppState) {
+    {
+        let mut notification_state = state.notification_state.lock().await;
+        let now = now_seconds();
+        if notification_state.digest_entries.is_empty() && notification_state.last_digest_sent == 0
+        {
+            // Start the first digest period now rather than sending right away
+            notification_state.last_digest_sent = now;
+        }
+        for pull_request in pull_requests {
+            notification_state
+                .digest_entries
+                .retain(|e| e.pull_request.id != pull_request.id);
+            notification_state.digest_entries.push(DigestEntry {
+                category: pull_request.category.clone(),
+                pull_request,
+                changed_at: now,
+            });
+        }
     }
-    if prs_approved.len() > 0 {
-        send_pull_request_notification(prs_approved, app_handle.clone(), "PRs approved");
+    persist_notification_state(state).await;
+}
+
+pub async fn persist_notification_state(state: &AppState) {
+    let notification_state = state.notification_state.lock().await.clone();
+    state.events.emit(AppEvent::NotificationStateUpdated(
+        NotificationStateUpdatedPayload {
+            state: notification_state,
+        },
+    ));
+}
+
+pub fn notifications_held(config: &AppConfig, notification_state: &NotificationState) -> bool {
+    hold_reason(config, notification_state).is_some()
+}
+
+fn hold_reason(config: &AppConfig, notification_state: &NotificationState) -> Option<HoldReason> {
+    if is_paused(notification_state) {
+        Some(HoldReason::Paused)
+    } else if is_quiet_now(&config.quiet_hours) {
+        Some(HoldReason::QuietHours)
+    } else {
+        None
     }
-    if prs_changes_requested.len() > 0 {
-        send_pull_request_notification(prs_changes_requested, app_handle.clone(), "PRs rejected");
+}
+
+pub fn is_snoozed(notification_state: &NotificationState, pull_request_id: u64) -> bool {
+    notification_state
+        .snoozed
+        .get(&pull_request_id)
+        .is_some_and(|until| *until > now_seconds())
+}
+
+pub fn is_paused(notification_state: &NotificationState) -> bool {
+    notification_state
+        .paused_until
+        .is_some_and(|paused_until| paused_until > now_seconds())
+}
+
+fn now_seconds() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+pub async fn start_scheduled_notifications_job(state: AppState) {
+    let mut interval = time::interval(Duration::from_secs(
+        SCHEDULED_NOTIFICATIONS_INTERVAL_SECONDS,
+    ));
+    loop {
+        interval.tick().await;
+        end_expired_pause(&state).await;
+        release_held_notifications(&state).await;
+        send_digest_if_due(&state).await;
+        send_email_digest_if_due(&state).await;
+        send_due_reminders(&state).await;
     }
-    if prs_missing_review.len() > 0 {
-        send_pull_request_notification(
-            prs_missing_review,
-            app_handle.clone(),
-            "PRs missing review",
-        );
+}
+
+// Clearing the pause publishes the state change, so the tray stops offering "Resume"
+async fn end_expired_pause(state: &AppState) {
+    {
+        let mut notification_state = state.notification_state.lock().await;
+        if notification_state.paused_until.is_none() || is_paused(&notification_state) {
+            return;
+        }
+        notification_state.paused_until = None;
     }
+    crate::log::info("Notification pause ended");
+    persist_notification_state(state).await;
 }
 
-fn send_pull_request_notification(
-    pull_requests: Vec<PullRequestItem>,
-    app_handle: tauri::AppHandle,
-    title: &str,
-) {
-    let body = if pull_requests.len() > 3 {
-        format!("{} {}", &pull_requests.len(), title)
+async fn release_held_notifications(state: &AppState) {
+    let config = state.config.lock().await.clone();
+    let held_notifications = {
+        let mut notification_state = state.notification_state.lock().await;
+        if notification_state.held_notifications.is_empty()
+            || notifications_held(&config, &notification_state)
+        {
+            return;
+        }
+        std::mem::take(&mut notification_state.held_notifications)
+    };
+    persist_notification_state(state).await;
+
+    let notification = DesktopNotification {
+        title: held_summary_title(&held_notifications).to_string(),
+        body: format_held_summary(&held_notifications),
+        urgency: NotificationUrgency::Normal,
+        icon: "dialog-information",
+        replace_key: None,
+        action: None,
+    };
+    let result = send_notification(state, &notification).await;
+    match result {
+        Ok(s) => crate::log::info(&format!("Held notifications summary sent: {}", s)),
+        Err(e) => crate::log::error(&format!("Failed to send held notifications: {}", e)),
+    }
+}
+
+fn held_summary_title(held_notifications: &[HeldNotification]) -> &'static str {
+    let held_by = |reason: HoldReason| held_notifications.iter().all(|h| h.reason == reason);
+    if held_by(HoldReason::Paused) {
+        "- While notifications were paused -"
+    } else if held_by(HoldReason::QuietHours) {
+        "- During quiet hours -"
     } else {
-        format!("{}", format_titles(&pull_requests))
+        "- While notifications were held -"
+    }
+}
+
+fn format_held_summary(held_notifications: &[HeldNotification]) -> String {
+    let pull_requests: Vec<(&PullRequestCategory, &PullRequestItem)> = held_notifications
+        .iter()
+        .flat_map(|h| h.pull_requests.iter().map(move |pr| (&h.category, pr)))
+        .collect();
+    format_category_counts(&pull_requests).join("\n")
+}
+
+// One line per category, e.g. "2 PRs approved", counting each PR once
+pub fn format_category_counts(
+    pull_requests: &[(&PullRequestCategory, &PullRequestItem)],
+) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for category_notification in NOTIFICATION_CATEGORIES.iter() {
+        let mut pr_ids: Vec<u64> = pull_requests
+            .iter()
+            .filter(|(category, _)| **category == category_notification.category)
+            .map(|(_, pr)| pr.id)
+            .collect();
+        pr_ids.sort();
+        pr_ids.dedup();
+        if !pr_ids.is_empty() {
+            lines.push(format!("{} {}", pr_ids.len(), category_notification.title));
+        }
+    }
+    lines
+}
+
+async fn send_digest_if_due(state: &AppState) {
+    let config = state.config.lock().await.clone();
+    if !config.digest.enabled {
+        return;
+    }
+    let digest_entries = {
+        let mut notification_state = state.notification_state.lock().await;
+        if notifications_held(&config, &notification_state)
+            || !is_digest_due(
+                &config.digest.cadence,
+                notification_state.last_digest_sent,
+                &chrono::Local::now(),
+            )
+        {
+            return;
+        }
+        notification_state.last_digest_sent = now_seconds();
+        std::mem::take(&mut notification_state.digest_entries)
     };
+    persist_notification_state(state).await;
 
-    let formatted_title = format!("- {} -", title);
+    if digest_entries.is_empty() {
+        return;
+    }
 
-    let result = send_notification(app_handle.clone(), &formatted_title, &body);
+    let notification = DesktopNotification {
+        title: "- PR digest -".to_string(),
+        body: format_digest(&digest_entries),
+        urgency: NotificationUrgency::Normal,
+        icon: "dialog-information",
+        replace_key: Some("digest".to_string()),
+        action: None,
+    };
+    let result = send_notification(state, &notification).await;
     match result {
-        Ok(s) => crate::log::info(&format!("Notification sent: {}", s)),
-        Err(e) => crate::log::error(&format!("Failed to send notification: {}", e)),
+        Ok(s) => crate::log::info(&format!("Digest sent: {}", s)),
+        Err(e) => crate::log::error(&format!("Failed to send digest: {}", e)),
+    }
+}
+
+pub fn should_notify(
+    settings: &NotificationSettingsV2,
+    category: &PullRequestCategory,
+    repo_name: &str,
+) -> bool {
+    let repo_setting = settings
+        .repo_overrides
+        .iter()
+        .filter(|o| matches_wildcard(&o.repo_name, repo_name))
+        .find_map(|o| o.categories.iter().find(|c| &c.category == category));
+    if let Some(setting) = repo_setting {
+        return setting.enabled;
+    }
+
+    let category_setting = settings.categories.iter().find(|c| &c.category == category);
+    if let Some(setting) = category_setting {
+        return setting.enabled;
     }
+
+    default_category_notification_settings()
+        .iter()
+        .find(|c| &c.category == category)
+        .is_some_and(|c| c.enabled)
 }
 
-fn send_notification(
-    app_handle: tauri::AppHandle,
-    title: &str,
-    body: &str,
+async fn send_pull_request_notification(
+    pull_requests: &[PullRequestItem],
+    state: &AppState,
+    category_notification: &CategoryNotification,
 ) -> Result<String, String> {
-    return if cfg!(target_os = "linux") {
-        send_notification_linux(title, body)
+    let title = category_notification.title;
+    let body = if pull_requests.len() > 3 {
+        format!("{} {}", &pull_requests.len(), title)
     } else {
-        send_notification_macos(app_handle.clone(), title, body)
+        format!("{}", format_titles(pull_requests))
     };
+
+    let action = match pull_requests {
+        [pull_request] => NotificationAction::OpenUrl {
+            url: pull_request.html_url.clone(),
+        },
+        _ => NotificationAction::FocusCategory {
+            category: category_notification.category.clone(),
+        },
+    };
+
+    let notification = DesktopNotification {
+        title: format!("- {} -", title),
+        body,
+        urgency: category_notification.urgency,
+        icon: category_notification.icon,
+        replace_key: Some(format!("{:?}", category_notification.category)),
+        action: Some(action),
+    };
+
+    send_notification(state, &notification).await
 }
 
-fn send_notification_macos(
-    app_handle: tauri::AppHandle,
-    title: &str,
-    body: &str,
+// The notification plugin needs a running app and has no click callbacks, urgency or
+// replace ids on desktop, so other platforms hand notifications to the GUI over the bus
+pub async fn send_notification(
+    state: &AppState,
+    notification: &DesktopNotification,
 ) -> Result<String, String> {
-    let result = app_handle
-        .notification()
-        .builder()
-        .title(title)
-        .body(body)
-        .auto_cancel()
-        .show();
-
-    return match result {
-        Ok(_) => Ok("Successfully sent notification".to_string()),
-        Err(e) => Err("Failed to send notification".to_string() + &e.to_string()),
-    };
+    #[cfg(target_os = "linux")]
+    {
+        let _ = state;
+        crate::linux_notifications::send_notification(notification).await
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        state.events.emit(AppEvent::ShowNotification(
+            crate::event_names::DesktopNotificationPayload {
+                title: notification.title.clone(),
+                body: notification.body.clone(),
+            },
+        ));
+        Ok("Notification handed to the app".to_string())
+    }
 }
 
-fn send_notification_linux(title: &str, body: &str) -> Result<String, String> {
-    let result = std::process::Command::new("notify-send")
-        .args([
-            "--app-name=pr-sentinel",
-            "--urgency=normal",
-            "--expire-time=5000",
-            "--hint=string:sound-name:message-new-instant",
-            &title,
-            &body,
-        ])
-        .output();
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum NotificationAction {
+    OpenUrl { url: String },
+    FocusCategory { category: PullRequestCategory },
+}
 
-    match result {
-        Ok(output) if output.status.success() => {
-            return Ok("Successfully sent notification with status: ".to_string()
-                + &output.status.to_string());
+pub fn handle_notification_action(state: &AppState, action: &NotificationAction) {
+    match action {
+        NotificationAction::OpenUrl { url } => {
+            open::that_detached(url).unwrap_or_else(|e| {
+                crate::log::error(&format!("Failed to open {}: {}", url, e));
+            });
         }
-        Ok(o) => {
-            return Err(
-                "Failed to send notification with status: ".to_string() + &o.status.to_string()
-            );
-        }
-        Err(e) => {
-            return Err(e.to_string());
+        NotificationAction::FocusCategory { category } => {
+            state
+                .events
+                .emit(AppEvent::FocusCategory(FocusCategoryPayload {
+                    category: category.clone(),
+                }));
         }
     }
 }
 
-fn format_titles(pull_requests: &Vec<PullRequestItem>) -> String {
-    let titles: Vec<&str> = pull_requests
+fn format_titles(pull_requests: &[PullRequestItem]) -> String {
+    let titles: Vec<String> = pull_requests
         .iter()
         .take(3)
-        .map(|pr| pr.title.as_str())
+        .map(|pr| {
+            if pr.is_code_owner {
+                format!("{} (you're a code owner)", pr.title)
+            } else {
+                pr.title.clone()
+            }
+        })
         .collect();
     return titles.join("\n\n");
 }
 
-#[tauri::command]
-pub fn test_notification(app_handle: tauri::AppHandle) -> Result<NotificationResult, ()> {
-    let result = send_notification(
-        app_handle.clone(),
-        "Test Notification",
-        "This is a test notification",
-    );
-
-    return match result {
-        Ok(s) => Ok(NotificationResult { status: s }),
-        Err(e) => Ok(NotificationResult { status: e }),
-    };
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotificationResult {
     pub status: String,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationStatus {
+    pub paused_until: Option<u64>,
+    pub quiet_hours_active: bool,
+    pub held_count: usize,
+}
+
+pub async fn get_status(state: &AppState) -> NotificationStatus {
+    let config = state.config.lock().await.clone();
+    let notification_state = state.notification_state.lock().await;
+    NotificationStatus {
+        paused_until: notification_state
+            .paused_until
+            .filter(|_| is_paused(&notification_state)),
+        quiet_hours_active: is_quiet_now(&config.quiet_hours),
+        held_count: notification_state.held_notifications.len(),
+    }
+}
+
+// Longer pauses and snoozes are rejected, the end has to stay a valid date in the UI
+const MAX_PAUSE_MINUTES: u64 = 365 * 24 * 60;
+
+fn minutes_from_now(minutes: u64) -> Result<u64, String> {
+    if minutes > MAX_PAUSE_MINUTES {
+        return Err(format!(
+            "Can't pause for more than {} minutes",
+            MAX_PAUSE_MINUTES
+        ));
+    }
+    Ok(now_seconds() + minutes * 60)
+}
+
+pub async fn pause(state: &AppState, minutes: u64) -> Result<(), String> {
+    let paused_until = minutes_from_now(minutes)?;
+    {
+        let mut notification_state = state.notification_state.lock().await;
+        notification_state.paused_until = Some(paused_until);
+    }
+    crate::log::info(&format!("Notifications paused for {} minutes", minutes));
+    persist_notification_state(state).await;
+    Ok(())
+}
+
+pub async fn resume(state: &AppState) {
+    {
+        let mut notification_state = state.notification_state.lock().await;
+        notification_state.paused_until = None;
+    }
+    crate::log::info("Notifications resumed");
+    persist_notification_state(state).await;
+    release_held_notifications(state).await;
+}
+
+pub async fn snooze(state: &AppState, pull_request_id: u64, minutes: u64) -> Result<(), String> {
+    let snoozed_until = minutes_from_now(minutes)?;
+    {
+        let mut notification_state = state.notification_state.lock().await;
+        notification_state
+            .snoozed
+            .insert(pull_request_id, snoozed_until);
+    }
+    crate::log::info(&format!(
+        "PR {} snoozed for {} minutes",
+        pull_request_id, minutes
+    ));
+    persist_notification_state(state).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::pull_request;
+
+    #[tokio::test]
+    async fn records_baseline_from_first_poll_with_pull_requests() {
+        let state = AppState::new().unwrap();
+
+        assert!(record_notified_pull_requests(&[], &state).await.is_empty());
+        assert!(!state.notification_state.lock().await.baseline_established);
+
+        let first_poll = [
+            pull_request(1, "Fix login", "ReviewRequested"),
+            pull_request(2, "Bump deps", "MinePending"),
+        ];
+        assert!(record_notified_pull_requests(&first_poll, &state)
+            .await
+            .is_empty());
+        assert!(state.notification_state.lock().await.baseline_established);
+
+        let second_poll = [
+            pull_request(1, "Fix login", "ReviewRequested"),
+            pull_request(2, "Bump deps", "MineApproved"),
+            pull_request(3, "Add export", "ReviewRequested"),
+        ];
+        let changed: Vec<u64> = record_notified_pull_requests(&second_poll, &state)
+            .await
+            .iter()
+            .map(|pr| pr.id)
+            .collect();
+        assert_eq!(changed, [2, 3]);
+    }
+}
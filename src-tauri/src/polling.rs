@@ -1,18 +1,20 @@
-use std::time::Duration;
-use tauri::{AppHandle, Emitter, Manager};
+use std::time::{Duration, Instant};
 use tokio::time;
 
 use crate::{
     app_state::{self, AppState},
-    event_names::EventNames,
+    event_bus::AppEvent,
     github_service::GithubClient,
+    metrics::PollErrorKind,
+    pull_requests::PULL_REQUEST_QUERY,
+    repo_rules::approver_teams,
 };
 
-const POLLING_INTERVAL_SECONDS: u64 = 600;
+pub const POLLING_INTERVAL_SECONDS: u64 = 600;
 const REFRESH_TIMEOUT_SECONDS: u64 = 30;
 
 // Polling job
-pub async fn start_polling_job(app_handle: AppHandle) {
+pub async fn start_polling_job(state: AppState) {
     let mut interval = time::interval(Duration::from_secs(POLLING_INTERVAL_SECONDS));
 
     crate::log::info("Starting polling job");
@@ -25,12 +27,13 @@ pub async fn start_polling_job(app_handle: AppHandle) {
         // Wait for the next interval tick
         interval.tick().await;
 
-        let app_handle_clone = app_handle.clone();
+        let started = Instant::now();
+        let polls = state.metrics.lock().await.poll_count();
 
         // Create a timeout future for the refresh operation
         match time::timeout(
             Duration::from_secs(REFRESH_TIMEOUT_SECONDS),
-            refresh_all_filters(app_handle_clone),
+            refresh_all_filters(state.clone()),
         )
         .await
         {
@@ -43,63 +46,76 @@ pub async fn start_polling_job(app_handle: AppHandle) {
                     "Refresh operation timed out after {}s",
                     REFRESH_TIMEOUT_SECONDS
                 ));
-                app_handle
-                    .emit(
-                        EventNames::POLLING_ERROR,
-                        serde_json::json!({
-                            "error": format!("Refresh operation timed out after {}s", REFRESH_TIMEOUT_SECONDS)
-                        }),
-                    )
-                    .unwrap_or_else(|e| {
-                        crate::log::error(&format!("Failed to emit error event: {}", e));
-                    });
+                {
+                    let mut metrics = state.metrics.lock().await;
+                    // Only when the search itself timed out, otherwise it's already recorded
+                    if metrics.poll_count() == polls {
+                        metrics.record_poll(started.elapsed());
+                    }
+                    metrics.record_poll_error(PollErrorKind::Timeout);
+                }
+                state.events.emit(AppEvent::PollingError(serde_json::json!({
+                    "error": format!("Refresh operation timed out after {}s", REFRESH_TIMEOUT_SECONDS)
+                })));
             }
         }
     }
 }
 
-pub async fn refresh_all_filters(app_handle: AppHandle) {
-    let state = app_handle
-        .try_state::<AppState>()
-        .expect("Failed to get app state");
-
-    let github_token = {
+pub async fn refresh_all_filters(state: AppState) {
+    let (github_token, username) = {
         let config = state.config.lock().await;
-        config.github_token.clone()
+        (
+            config.github_token.clone(),
+            config.username.clone().unwrap_or_default(),
+        )
     };
 
     let Some(ok_token) = github_token else {
         crate::log::error("No github token found");
+        state
+            .metrics
+            .lock()
+            .await
+            .record_poll_error(PollErrorKind::NoToken);
         return;
     };
 
     let client = GithubClient::new(ok_token);
 
-    match client
-        .search_pull_requests("is:pr is:open involves:@me draft:false".to_string())
+    let started = Instant::now();
+    let result = client
+        .search_pull_requests(PULL_REQUEST_QUERY.to_string(), &username)
+        .await;
+    state.metrics.lock().await.record_poll(started.elapsed());
+
+    state
+        .metrics
+        .lock()
         .await
-    {
+        .record_rate_limits(client.rate_limits());
+
+    match result {
         Ok(response) => {
-            app_state::new_pull_request_response(&app_handle, &response).await;
+            let teams = {
+                let config = state.config.lock().await;
+                let team_policy = state.team_policy.lock().await;
+                approver_teams(&response, &config, &team_policy)
+            };
+            let team_members = client.get_team_members(&teams).await;
+            app_state::new_pull_request_response(&state, &response, &team_members).await;
         }
         Err(e) => {
             crate::log::error(&format!("Error polling pull requests: {}", e));
+            state
+                .metrics
+                .lock()
+                .await
+                .record_poll_error(PollErrorKind::Github);
             // Emit error event
-            app_handle
-                .emit(
-                    EventNames::POLLING_ERROR,
-                    serde_json::json!({
-                        "error": e.to_string()
-                    }),
-                )
-                .unwrap_or_else(|e| {
-                    crate::log::error(&format!("Failed to emit error event: {}", e));
-                });
+            state.events.emit(AppEvent::PollingError(serde_json::json!({
+                "error": e.to_string()
+            })));
         }
     }
 }
-
-#[tauri::command]
-pub async fn refresh(app_handle: AppHandle) {
-    refresh_all_filters(app_handle).await;
-}
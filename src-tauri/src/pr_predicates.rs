@@ -4,25 +4,44 @@ use octocrab::models::pulls::{Review, ReviewState};
 
 use crate::{
     app_data::PullRequestCategory,
-    github_service::{get_owner_and_repo, GithubPRWithReviews},
+    github_service::GithubPRWithReviews,
+    repo_rules::{CodeOwnerGroup, RepoRules},
     AppConfig,
 };
 
 // Type alias for the function signature
 pub struct CategoryPredicate {
-    pub predicate: fn(&GithubPRWithReviews, &AppConfig) -> bool,
+    pub predicate: fn(&GithubPRWithReviews, &AppConfig, &RepoRules) -> bool,
     pub category: PullRequestCategory,
 }
 
-fn mine_pending(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> bool {
+fn mine_pending(
+    pr_with_reviews: &GithubPRWithReviews,
+    config: &AppConfig,
+    _rules: &RepoRules,
+) -> bool {
     return is_mine(pr_with_reviews, config);
 }
 
-fn mine_approved(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> bool {
+fn mine_approved(
+    pr_with_reviews: &GithubPRWithReviews,
+    config: &AppConfig,
+    rules: &RepoRules,
+) -> bool {
     if !is_mine(pr_with_reviews, config) {
         return false;
     }
-    let needed_approvals = needed_approvals(pr_with_reviews, config);
+    return is_approved(pr_with_reviews, rules, false);
+}
+
+// With `include_stale`, approvals on older commits count even when the repository dismisses
+// them on push
+fn is_approved(
+    pr_with_reviews: &GithubPRWithReviews,
+    rules: &RepoRules,
+    include_stale: bool,
+) -> bool {
+    let needed_approvals = rules.needed_approvals;
 
     let all_latest_reviews = latest_reviews(pr_with_reviews);
 
@@ -34,14 +53,26 @@ fn mine_approved(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> b
         return false;
     }
 
+    if !code_owners_without_approval(pr_with_reviews, rules, include_stale).is_empty() {
+        return false;
+    }
+
     return all_latest_reviews
         .iter()
-        .filter(|r| review_is(r, ReviewState::Approved, true))
+        .filter(|r| {
+            review_is(r, ReviewState::Approved, true)
+                && rules.counts_review(&r.0)
+                && (include_stale || !is_stale(pr_with_reviews, &r.0, rules))
+        })
         .count()
         >= needed_approvals;
 }
 
-fn mine_changes_requested(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> bool {
+fn mine_changes_requested(
+    pr_with_reviews: &GithubPRWithReviews,
+    config: &AppConfig,
+    _rules: &RepoRules,
+) -> bool {
     if !is_mine(pr_with_reviews, config) {
         return false;
     }
@@ -51,7 +82,11 @@ fn mine_changes_requested(pr_with_reviews: &GithubPRWithReviews, config: &AppCon
         .any(|r| review_is(r, ReviewState::ChangesRequested, true));
 }
 
-fn re_review(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> bool {
+fn re_review(
+    pr_with_reviews: &GithubPRWithReviews,
+    config: &AppConfig,
+    _rules: &RepoRules,
+) -> bool {
     if is_mine(pr_with_reviews, config) {
         return false;
     }
@@ -63,22 +98,35 @@ fn re_review(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> bool
     }
     let user_latest_review = get_latest_review(&reviews_by_user[&username]);
     return is_user_review_requested(pr_with_reviews, &username)
-        || user_latest_review.state == Some(ReviewState::Dismissed);
+        || user_latest_review.state == Some(ReviewState::Dismissed)
+        || (config.rereview_on_new_commits
+            && commits_since_review(pr_with_reviews, &user_latest_review).is_some());
 }
 
-fn review_missing(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> bool {
+fn review_missing(
+    pr_with_reviews: &GithubPRWithReviews,
+    config: &AppConfig,
+    rules: &RepoRules,
+) -> bool {
     if is_mine(pr_with_reviews, config) {
         return false;
     }
-    let needed_approvals = needed_approvals(pr_with_reviews, config);
+    let needed_approvals = rules.needed_approvals;
     let reviews_by_user = group_by_user(pr_with_reviews.reviews.clone());
-    let unique_reviewers = reviews_by_user.keys().len();
+    let unique_reviewers = reviews_by_user
+        .values()
+        .filter(|reviews| reviews.iter().any(|r| rules.counts_review(r)))
+        .count();
     let username = config.username.clone().unwrap_or("".to_string());
     let current_user_has_reviewed = reviews_by_user.contains_key(&username);
     return unique_reviewers < needed_approvals && !current_user_has_reviewed;
 }
 
-fn review_requested(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> bool {
+fn review_requested(
+    pr_with_reviews: &GithubPRWithReviews,
+    config: &AppConfig,
+    _rules: &RepoRules,
+) -> bool {
     return !is_mine(pr_with_reviews, config);
 }
 
@@ -109,6 +157,101 @@ pub static PR_CATEGORIES: &[CategoryPredicate] = &[
     },
 ];
 
+// Code owner groups none of whose members approved yet
+pub fn pending_code_owners<'a>(
+    pr_with_reviews: &GithubPRWithReviews,
+    rules: &'a RepoRules,
+) -> Vec<&'a CodeOwnerGroup> {
+    code_owners_without_approval(pr_with_reviews, rules, false)
+}
+
+fn code_owners_without_approval<'a>(
+    pr_with_reviews: &GithubPRWithReviews,
+    rules: &'a RepoRules,
+    include_stale: bool,
+) -> Vec<&'a CodeOwnerGroup> {
+    let approvers: Vec<String> = latest_reviews(pr_with_reviews)
+        .iter()
+        .filter(|r| {
+            review_is(r, ReviewState::Approved, true)
+                && (include_stale || !is_stale(pr_with_reviews, &r.0, rules))
+        })
+        .filter_map(|r| r.0.user.as_ref().map(|user| user.login.clone()))
+        .collect();
+    rules
+        .code_owners
+        .iter()
+        .filter(|group| !approvers.iter().any(|login| group.includes(login)))
+        .collect()
+}
+
+// Shown for my pending PRs that only lack approvals because they went stale
+pub fn pending_reason(pr_with_reviews: &GithubPRWithReviews, rules: &RepoRules) -> Option<String> {
+    if !is_approved(pr_with_reviews, rules, false) && is_approved(pr_with_reviews, rules, true) {
+        return Some("approval stale after push".to_string());
+    }
+    None
+}
+
+// Shown for PRs to rereview that got new commits after my latest review
+pub fn rereview_reason(
+    pr_with_reviews: &GithubPRWithReviews,
+    config: &AppConfig,
+) -> Option<String> {
+    if !config.rereview_on_new_commits {
+        return None;
+    }
+    let reviews_by_user = group_by_user(pr_with_reviews.reviews.clone());
+    let username = config.username.clone().unwrap_or("".to_string());
+    let user_reviews = reviews_by_user.get(&username)?;
+    let user_latest_review = get_latest_review(user_reviews);
+    match commits_since_review(pr_with_reviews, &user_latest_review)? {
+        Some(1) => Some("1 new commit since your review".to_string()),
+        Some(count) => Some(format!("{} new commits since your review", count)),
+        None => Some("new commits since your review".to_string()),
+    }
+}
+
+// None when the head is still the reviewed commit or either is unknown. Otherwise the
+// number of commits after the reviewed one, None inside when the reviewed commit is gone
+// from the PR, e.g. after a force push.
+fn commits_since_review(
+    pr_with_reviews: &GithubPRWithReviews,
+    review: &Review,
+) -> Option<Option<usize>> {
+    let commit_id = review.commit_id.as_deref()?;
+    let head_sha = pr_with_reviews
+        .pull_request
+        .as_ref()
+        .map(|pr| pr.head.sha.as_str())?;
+    if commit_id == head_sha {
+        return None;
+    }
+    Some(
+        pr_with_reviews
+            .commits
+            .iter()
+            .position(|sha| sha == commit_id)
+            .map(|index| pr_with_reviews.commits.len() - index - 1),
+    )
+}
+
+// A review of an older commit, when the repository dismisses those on push. Reviews are
+// only stale when both commits are known.
+fn is_stale(pr_with_reviews: &GithubPRWithReviews, review: &Review, rules: &RepoRules) -> bool {
+    if !rules.dismiss_stale_approvals {
+        return false;
+    }
+    let head_sha = pr_with_reviews
+        .pull_request
+        .as_ref()
+        .map(|pr| pr.head.sha.as_str());
+    match (review.commit_id.as_deref(), head_sha) {
+        (Some(commit_id), Some(head_sha)) => commit_id != head_sha,
+        _ => false,
+    }
+}
+
 fn group_by_user(reviews: Vec<Review>) -> HashMap<String, Vec<Review>> {
     let mut reviews_by_user = HashMap::new();
     for review in reviews {
@@ -167,26 +310,10 @@ fn latest_reviews(pr_with_reviews: &GithubPRWithReviews) -> Vec<(Review, bool)>
     return all_latest_reviews;
 }
 
-fn is_mine(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> bool {
+pub fn is_mine(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> bool {
     return pr_with_reviews.pr.user.login == config.username.clone().unwrap_or("".to_string());
 }
 
-fn pr_repo_name(pr_with_reviews: &GithubPRWithReviews) -> String {
-    let (owner, repo) = get_owner_and_repo(&pr_with_reviews.pr.repository_url.to_string());
-
-    return format!("{}/{}", owner, repo);
-}
-
-fn needed_approvals(pr_with_reviews: &GithubPRWithReviews, config: &AppConfig) -> usize {
-    let pr_repo_name = pr_repo_name(pr_with_reviews);
-    return config
-        .repo_config
-        .iter()
-        .find(|r| r.repo_name == pr_repo_name)
-        .map(|f| f.needed_approvals)
-        .unwrap_or(1);
-}
-
 fn review_is(
     review: &(Review, bool),
     state: ReviewState,
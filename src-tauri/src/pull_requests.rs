@@ -0,0 +1,102 @@
+use crate::{
+    app_data::{PullRequestCategory, PullRequestItem, PullRequestsData},
+    github_service::GithubPRWithReviews,
+    mute_rules::is_muted,
+    pr_predicates::{is_mine, pending_code_owners, pending_reason, rereview_reason, PR_CATEGORIES},
+    repo_rules::{resolve_repo_rules, RepoRules, TeamMembers},
+    team_policy::TeamPolicy,
+    AppConfig,
+};
+
+// Everything the GUI and the CLI show is fetched with this query
+pub const PULL_REQUEST_QUERY: &str = "is:pr is:open involves:@me draft:false";
+
+pub fn build_pull_requests_data(
+    response: &[GithubPRWithReviews],
+    config: &AppConfig,
+    team_policy: &TeamPolicy,
+    team_members: &TeamMembers,
+) -> PullRequestsData {
+    PullRequestsData {
+        last_updated: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        pull_requests: response
+            .iter()
+            .map(|r| map_to_app_data(r, config, team_policy, team_members))
+            .collect(),
+    }
+}
+
+fn get_category_from_reviews(
+    pr_with_reviews: &GithubPRWithReviews,
+    config: &AppConfig,
+    rules: &RepoRules,
+) -> PullRequestCategory {
+    for category in PR_CATEGORIES.iter() {
+        if (category.predicate)(pr_with_reviews, config, rules) {
+            return category.category.clone();
+        }
+    }
+    crate::log::error(&format!(
+        "No category found for PR: {}",
+        pr_with_reviews.pr.id
+    ));
+    return PullRequestCategory::MinePending;
+}
+
+fn map_to_app_data(
+    github_pr_with_reviews: &GithubPRWithReviews,
+    config: &AppConfig,
+    team_policy: &TeamPolicy,
+    team_members: &TeamMembers,
+) -> PullRequestItem {
+    let rules = resolve_repo_rules(github_pr_with_reviews, config, team_policy, team_members);
+    let pending_code_owners = pending_code_owners(github_pr_with_reviews, &rules);
+    let username = config.username.clone().unwrap_or_default();
+    let is_code_owner = !is_mine(github_pr_with_reviews, config)
+        && pending_code_owners
+            .iter()
+            .any(|group| group.includes(&username));
+
+    let category = get_category_from_reviews(github_pr_with_reviews, config, &rules);
+    let reason = match category {
+        PullRequestCategory::MinePending => pending_reason(github_pr_with_reviews, &rules),
+        PullRequestCategory::Rereview => rereview_reason(github_pr_with_reviews, config),
+        _ => None,
+    };
+
+    PullRequestItem {
+        id: github_pr_with_reviews.pr.id.to_be(),
+        title: github_pr_with_reviews.pr.title.clone(),
+        url: github_pr_with_reviews.pr.url.to_string(),
+        repository_url: github_pr_with_reviews.pr.repository_url.to_string(),
+        login: github_pr_with_reviews.pr.user.login.clone(),
+        avatar_url: github_pr_with_reviews.pr.user.avatar_url.to_string(),
+        html_url: github_pr_with_reviews.pr.html_url.to_string(),
+        created_at: github_pr_with_reviews.pr.created_at.to_rfc3339(),
+        updated_at: github_pr_with_reviews.pr.updated_at.to_rfc3339(),
+        is_assigned: github_pr_with_reviews
+            .pr
+            .assignees
+            .iter()
+            .any(|a| a.login == config.username.clone().unwrap_or("".to_string())),
+        category,
+        muted: is_muted(github_pr_with_reviews, config),
+        base_ref: github_pr_with_reviews
+            .pull_request
+            .as_ref()
+            .map(|pr| pr.base.ref_field.clone()),
+        head_ref: github_pr_with_reviews
+            .pull_request
+            .as_ref()
+            .map(|pr| pr.head.ref_field.clone()),
+        pending_code_owners: pending_code_owners
+            .iter()
+            .map(|group| group.owners.clone())
+            .collect(),
+        is_code_owner,
+        reason,
+    }
+}
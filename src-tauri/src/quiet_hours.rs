@@ -0,0 +1,135 @@
+use chrono::{DateTime, Datelike, Local, NaiveTime, TimeZone};
+
+use crate::app_data_v2::{QuietHoursV2, QuietHoursWindowV2};
+
+pub fn is_quiet_time<Tz: TimeZone>(quiet_hours: &QuietHoursV2, now: &DateTime<Tz>) -> bool {
+    if !quiet_hours.enabled {
+        return false;
+    }
+    quiet_hours
+        .windows
+        .iter()
+        .any(|window| is_within_window(window, now))
+}
+
+pub fn is_quiet_now(quiet_hours: &QuietHoursV2) -> bool {
+    is_quiet_time(quiet_hours, &Local::now())
+}
+
+// A window where end is before start runs past midnight and belongs to the day it starts on.
+// A window where start and end are equal covers the whole day.
+fn is_within_window<Tz: TimeZone>(window: &QuietHoursWindowV2, now: &DateTime<Tz>) -> bool {
+    let (Some(start), Some(end)) = (parse_time(&window.start), parse_time(&window.end)) else {
+        crate::log::error(&format!(
+            "Invalid quiet hours window: {} - {}",
+            window.start, window.end
+        ));
+        return false;
+    };
+
+    let today = now.weekday();
+    let yesterday = today.pred();
+    let time = now.time();
+
+    if start == end {
+        return window.days.contains(&today);
+    }
+    if start < end {
+        return window.days.contains(&today) && time >= start && time < end;
+    }
+    (window.days.contains(&today) && time >= start)
+        || (window.days.contains(&yesterday) && time < end)
+}
+
+pub fn validate_quiet_hours(quiet_hours: &QuietHoursV2) -> Result<(), String> {
+    for window in &quiet_hours.windows {
+        for time in [&window.start, &window.end] {
+            if parse_time(time).is_none() {
+                return Err(format!("Invalid quiet hours time {}, expected HH:MM", time));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, Weekday};
+
+    use super::*;
+
+    fn window(days: &[Weekday], start: &str, end: &str) -> QuietHoursWindowV2 {
+        QuietHoursWindowV2 {
+            days: days.to_vec(),
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    // 2024-03-18 is a Monday
+    fn at(date_time: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_str(&format!("{} +01:00", date_time), "%Y-%m-%d %H:%M %z").unwrap()
+    }
+
+    #[test]
+    fn matches_windows() {
+        let evening = window(&[Weekday::Mon], "18:00", "22:00");
+        let overnight = window(&[Weekday::Mon], "22:00", "07:00");
+        let all_day = window(&[Weekday::Sat], "00:00", "00:00");
+        let cases = [
+            (&evening, "2024-03-18 17:59", false),
+            (&evening, "2024-03-18 18:00", true),
+            (&evening, "2024-03-18 21:59", true),
+            (&evening, "2024-03-18 22:00", false),
+            (&evening, "2024-03-19 19:00", false),
+            (&overnight, "2024-03-18 21:59", false),
+            (&overnight, "2024-03-18 22:00", true),
+            (&overnight, "2024-03-18 23:59", true),
+            (&overnight, "2024-03-19 00:00", true),
+            (&overnight, "2024-03-19 06:59", true),
+            (&overnight, "2024-03-19 07:00", false),
+            (&overnight, "2024-03-18 03:00", false),
+            (&overnight, "2024-03-19 23:00", false),
+            (&all_day, "2024-03-23 00:00", true),
+            (&all_day, "2024-03-23 23:59", true),
+            (&all_day, "2024-03-24 00:00", false),
+        ];
+        for (window, now, expected) in cases {
+            assert_eq!(
+                is_within_window(window, &at(now)),
+                expected,
+                "{} - {} at {}",
+                window.start,
+                window.end,
+                now
+            );
+        }
+    }
+
+    #[test]
+    fn only_checks_windows_when_enabled() {
+        let mut quiet_hours = QuietHoursV2 {
+            enabled: false,
+            windows: vec![window(&[Weekday::Mon], "18:00", "22:00")],
+        };
+        assert!(!is_quiet_time(&quiet_hours, &at("2024-03-18 19:00")));
+        quiet_hours.enabled = true;
+        assert!(is_quiet_time(&quiet_hours, &at("2024-03-18 19:00")));
+    }
+
+    #[test]
+    fn rejects_times_that_are_not_hh_mm() {
+        let quiet_hours = |start: &str, end: &str| QuietHoursV2 {
+            enabled: true,
+            windows: vec![window(&[Weekday::Mon], start, end)],
+        };
+        assert!(validate_quiet_hours(&quiet_hours("22:00", "07:00")).is_ok());
+        assert!(validate_quiet_hours(&quiet_hours("22", "07:00")).is_err());
+        assert!(validate_quiet_hours(&quiet_hours("22:00", "25:00")).is_err());
+        assert!(validate_quiet_hours(&quiet_hours("10pm", "07:00")).is_err());
+    }
+}
@@ -0,0 +1,417 @@
+use chrono::{DateTime, Datelike, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_data_v2::{ReminderRuleV2, WorkingHoursV2},
+    notifications::{
+        is_snoozed, notifications_held, persist_notification_state, send_notification,
+        DesktopNotification, NotificationAction, NotificationUrgency, NOTIFICATION_CATEGORIES,
+    },
+    quiet_hours::parse_time,
+    AppState, PullRequestItem,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderState {
+    pub rule_id: uuid::Uuid,
+    pub since: u64,
+    pub last_reminded: u64,
+    pub count: u32,
+}
+
+// A rule reminding every 0 hours would send a reminder on every tick
+pub fn validate_reminder_rules(reminder_rules: &[ReminderRuleV2]) -> Result<(), String> {
+    if reminder_rules.iter().any(|rule| rule.interval_hours == 0) {
+        return Err("Reminder interval must be at least 1 hour".to_string());
+    }
+    Ok(())
+}
+
+// Times are parsed on every tick, a typo would otherwise only show up in the log
+pub fn validate_working_hours(working_hours: &WorkingHoursV2) -> Result<(), String> {
+    for time in [&working_hours.start, &working_hours.end] {
+        if parse_time(time).is_none() {
+            return Err(format!(
+                "Invalid working hours time {}, expected HH:MM",
+                time
+            ));
+        }
+    }
+    Ok(())
+}
+
+struct DueReminder {
+    pull_request: PullRequestItem,
+    urgency: NotificationUrgency,
+}
+
+pub async fn send_due_reminders(state: &AppState) {
+    let config = state.config.lock().await.clone();
+    let pull_requests = state.data.lock().await.pull_requests.pull_requests.clone();
+    let now = Local::now();
+    let now_seconds = now.timestamp() as u64;
+
+    let (due_reminders, changed) = {
+        let mut notification_state = state.notification_state.lock().await;
+        let held = notifications_held(&config, &notification_state);
+        let snoozed: Vec<u64> = pull_requests
+            .iter()
+            .filter(|pr| is_snoozed(&notification_state, pr.id))
+            .map(|pr| pr.id)
+            .collect();
+        let reminders = &mut notification_state.reminders;
+
+        let active: Vec<(&PullRequestItem, &ReminderRuleV2)> = pull_requests
+            .iter()
+            .filter(|pr| !pr.muted && !snoozed.contains(&pr.id))
+            .filter_map(|pr| {
+                config
+                    .reminder_rules
+                    .iter()
+                    .find(|rule| {
+                        rule.enabled
+                            && rule.interval_hours > 0
+                            && rule.categories.contains(&pr.category)
+                    })
+                    .map(|rule| (pr, rule))
+            })
+            .collect();
+
+        let reminder_count = reminders.len();
+        reminders.retain(|id, reminder| {
+            active
+                .iter()
+                .any(|(pr, rule)| pr.id == *id && rule.id == reminder.rule_id)
+        });
+        let mut changed = reminders.len() != reminder_count;
+
+        let mut due_reminders: Vec<DueReminder> = Vec::new();
+        for (pr, rule) in active {
+            changed |= !reminders.contains_key(&pr.id);
+            let reminder = reminders.entry(pr.id).or_insert(ReminderState {
+                rule_id: rule.id,
+                since: now_seconds,
+                last_reminded: now_seconds,
+                count: 0,
+            });
+            let waited =
+                working_seconds_between(&config.working_hours, reminder.last_reminded, now_seconds);
+            if held || waited < rule.interval_hours * 60 * 60 {
+                continue;
+            }
+            reminder.count += 1;
+            reminder.last_reminded = now_seconds;
+            due_reminders.push(DueReminder {
+                pull_request: pr.clone(),
+                urgency: if reminder.count > rule.escalate_after {
+                    NotificationUrgency::Critical
+                } else {
+                    NotificationUrgency::Normal
+                },
+            });
+        }
+        changed |= !due_reminders.is_empty();
+        (due_reminders, changed)
+    };
+    // Runs every minute, only write the file when a reminder started, ended or was sent
+    if changed {
+        persist_notification_state(state).await;
+    }
+
+    if due_reminders.is_empty() {
+        return;
+    }
+
+    let result = send_notification(state, &reminder_notification(&due_reminders)).await;
+    match result {
+        Ok(s) => crate::log::info(&format!("Reminder sent: {}", s)),
+        Err(e) => crate::log::error(&format!("Failed to send reminder: {}", e)),
+    }
+}
+
+fn reminder_notification(due_reminders: &[DueReminder]) -> DesktopNotification {
+    let urgency = if due_reminders
+        .iter()
+        .any(|r| r.urgency == NotificationUrgency::Critical)
+    {
+        NotificationUrgency::Critical
+    } else {
+        NotificationUrgency::Normal
+    };
+
+    let action = match due_reminders {
+        [reminder] => NotificationAction::OpenUrl {
+            url: reminder.pull_request.html_url.clone(),
+        },
+        _ => NotificationAction::FocusCategory {
+            category: due_reminders[0].pull_request.category.clone(),
+        },
+    };
+
+    let titles: Vec<&str> = due_reminders
+        .iter()
+        .take(3)
+        .map(|r| r.pull_request.title.as_str())
+        .collect();
+    let body = if due_reminders.len() > 3 {
+        format!("{} PRs still waiting on you", due_reminders.len())
+    } else {
+        titles.join("\n\n")
+    };
+
+    let icon = NOTIFICATION_CATEGORIES
+        .iter()
+        .find(|n| n.category == due_reminders[0].pull_request.category)
+        .map(|n| n.icon)
+        .unwrap_or("dialog-information");
+
+    DesktopNotification {
+        title: "- Reminder: PRs waiting on you -".to_string(),
+        body,
+        urgency,
+        icon,
+        replace_key: Some("reminder".to_string()),
+        action: Some(action),
+    }
+}
+
+// Seconds between two timestamps that fall inside the configured working hours
+pub fn working_seconds_between(working_hours: &WorkingHoursV2, from: u64, to: u64) -> u64 {
+    working_seconds_in(working_hours, from, to, &Local)
+}
+
+// Like quiet hours, an end at or before the start runs past midnight and belongs to the day it
+// starts on
+fn working_seconds_in<Tz: TimeZone>(
+    working_hours: &WorkingHoursV2,
+    from: u64,
+    to: u64,
+    timezone: &Tz,
+) -> u64 {
+    let (Some(start), Some(end)) = (
+        parse_time(&working_hours.start),
+        parse_time(&working_hours.end),
+    ) else {
+        crate::log::error("Invalid working hours, counting all hours");
+        return to.saturating_sub(from);
+    };
+    let (Some(from), Some(to)) = (
+        timezone.timestamp_opt(from as i64, 0).single(),
+        timezone.timestamp_opt(to as i64, 0).single(),
+    ) else {
+        return 0;
+    };
+
+    let mut total: i64 = 0;
+    // A window that started the day before can still be running at `from`
+    let Some(mut date) = from.date_naive().pred_opt() else {
+        return 0;
+    };
+    while date <= to.date_naive() {
+        let Some(next) = date.succ_opt() else {
+            break;
+        };
+        if working_hours.days.contains(&date.weekday()) {
+            let end_date = if end <= start { next } else { date };
+            let day_start = local_time(timezone, date.and_time(start));
+            let day_end = local_time(timezone, end_date.and_time(end));
+            if let (Some(day_start), Some(day_end)) = (day_start, day_end) {
+                let overlap_start = day_start.max(from.clone());
+                let overlap_end = day_end.min(to.clone());
+                if overlap_end > overlap_start {
+                    total += (overlap_end - overlap_start).num_seconds();
+                }
+            }
+        }
+        date = next;
+    }
+    total.max(0) as u64
+}
+
+fn local_time<Tz: TimeZone>(
+    timezone: &Tz,
+    date_time: chrono::NaiveDateTime,
+) -> Option<DateTime<Tz>> {
+    timezone.from_local_datetime(&date_time).earliest()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{
+        FixedOffset, MappedLocalTime, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Weekday,
+    };
+
+    use super::*;
+
+    // Central European time, switching to summer time on 2024-03-31 at 02:00 local time
+    #[derive(Debug, Clone, Copy)]
+    struct CentralEurope;
+
+    impl CentralEurope {
+        fn switch() -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2024, 3, 31)
+                .unwrap()
+                .and_hms_opt(1, 0, 0)
+                .unwrap()
+        }
+
+        fn winter() -> FixedOffset {
+            FixedOffset::east_opt(60 * 60).unwrap()
+        }
+
+        fn summer() -> FixedOffset {
+            FixedOffset::east_opt(2 * 60 * 60).unwrap()
+        }
+    }
+
+    impl TimeZone for CentralEurope {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self {
+            CentralEurope
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> MappedLocalTime<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(
+            &self,
+            local: &NaiveDateTime,
+        ) -> MappedLocalTime<FixedOffset> {
+            let is_winter = *local - TimeDelta::hours(1) < Self::switch();
+            let is_summer = *local - TimeDelta::hours(2) >= Self::switch();
+            match (is_winter, is_summer) {
+                (true, true) => MappedLocalTime::Ambiguous(Self::winter(), Self::summer()),
+                (true, false) => MappedLocalTime::Single(Self::winter()),
+                (false, true) => MappedLocalTime::Single(Self::summer()),
+                (false, false) => MappedLocalTime::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            if *utc < Self::switch() {
+                Self::winter()
+            } else {
+                Self::summer()
+            }
+        }
+    }
+
+    const WEEKDAYS: [Weekday; 5] = [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+    ];
+
+    fn working_hours(days: &[Weekday], start: &str, end: &str) -> WorkingHoursV2 {
+        WorkingHoursV2 {
+            days: days.to_vec(),
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    fn timestamp(date_time: &str) -> u64 {
+        let date_time = NaiveDateTime::parse_from_str(date_time, "%Y-%m-%d %H:%M").unwrap();
+        CentralEurope
+            .from_local_datetime(&date_time)
+            .unwrap()
+            .timestamp() as u64
+    }
+
+    const HOUR: u64 = 60 * 60;
+
+    #[test]
+    fn counts_only_working_hours() {
+        let office = working_hours(&WEEKDAYS, "09:00", "17:00");
+        let night_shift = working_hours(&WEEKDAYS, "22:00", "06:00");
+        // 2024-03-22 is a Friday, 2024-03-25 a Monday, 2024-03-31 the switch to summer time
+        let cases = [
+            (
+                &office,
+                "2024-03-19 10:00",
+                "2024-03-19 12:30",
+                2 * HOUR + HOUR / 2,
+            ),
+            (&office, "2024-03-19 07:00", "2024-03-19 20:00", 8 * HOUR),
+            (&office, "2024-03-19 18:00", "2024-03-20 08:00", 0),
+            (&office, "2024-03-19 16:00", "2024-03-20 10:00", 2 * HOUR),
+            (&office, "2024-03-22 16:00", "2024-03-25 10:00", 2 * HOUR),
+            (&office, "2024-03-23 10:00", "2024-03-24 16:00", 0),
+            (&office, "2024-03-29 16:00", "2024-04-01 10:00", 2 * HOUR),
+            (&office, "2024-03-19 12:00", "2024-03-19 10:00", 0),
+            (
+                &night_shift,
+                "2024-03-19 20:00",
+                "2024-03-20 08:00",
+                8 * HOUR,
+            ),
+            (
+                &night_shift,
+                "2024-03-20 03:00",
+                "2024-03-20 12:00",
+                3 * HOUR,
+            ),
+            (
+                &night_shift,
+                "2024-03-22 23:00",
+                "2024-03-23 23:00",
+                7 * HOUR,
+            ),
+            (
+                &night_shift,
+                "2024-03-24 12:00",
+                "2024-03-26 12:00",
+                8 * HOUR,
+            ),
+        ];
+        for (working_hours, from, to, expected) in cases {
+            assert_eq!(
+                working_seconds_in(
+                    working_hours,
+                    timestamp(from),
+                    timestamp(to),
+                    &CentralEurope
+                ),
+                expected,
+                "{} - {} from {} to {}",
+                working_hours.start,
+                working_hours.end,
+                from,
+                to
+            );
+        }
+    }
+
+    #[test]
+    fn counts_real_time_across_a_dst_change() {
+        let weekend_nights = working_hours(&[Weekday::Sat], "22:00", "06:00");
+        assert_eq!(
+            working_seconds_in(
+                &weekend_nights,
+                timestamp("2024-03-30 20:00"),
+                timestamp("2024-03-31 12:00"),
+                &CentralEurope
+            ),
+            7 * HOUR
+        );
+
+        let sundays = working_hours(&[Weekday::Sun], "00:00", "00:00");
+        assert_eq!(
+            working_seconds_in(
+                &sundays,
+                timestamp("2024-03-30 12:00"),
+                timestamp("2024-04-01 12:00"),
+                &CentralEurope
+            ),
+            23 * HOUR
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use octocrab::models::{pulls::Review, AuthorAssociation};
+
+use crate::{
+    app_data_v2::RepoConfigV2,
+    github_service::{get_owner_and_repo, get_repo_name, GithubPRWithReviews},
+    team_policy::TeamPolicy,
+    wildcard::matches_wildcard,
+    AppConfig,
+};
+
+const DEFAULT_NEEDED_APPROVALS: usize = 1;
+const DEFAULT_DISMISS_STALE_APPROVALS: bool = false;
+
+// Logins of the members of each `org/team` named in an approver allowlist or CODEOWNERS.
+// Teams whose members couldn't be read are missing.
+pub type TeamMembers = HashMap<String, Vec<String>>;
+
+// The rules a PR is categorized with
+#[derive(Debug, Clone)]
+pub struct RepoRules {
+    pub needed_approvals: usize,
+    // Approvals on commits other than the head commit don't count
+    pub dismiss_stale_approvals: bool,
+    // None when every approval counts
+    pub approvers: Option<ApproverAllowlist>,
+    // Every group has to approve. Teams whose members couldn't be read match nobody, so a
+    // group of only such teams keeps the PR from being approved.
+    pub code_owners: Vec<CodeOwnerGroup>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CodeOwnerGroup {
+    // As written in CODEOWNERS, e.g. `@alice` or `@myorg/backend`
+    pub owners: Vec<String>,
+    // The users among them and the members of the teams among them
+    pub logins: Vec<String>,
+}
+
+impl CodeOwnerGroup {
+    pub fn includes(&self, login: &str) -> bool {
+        self.logins
+            .iter()
+            .any(|owner| owner.eq_ignore_ascii_case(login))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApproverAllowlist {
+    // Listed users and the members of listed teams, users may contain wildcards
+    pub logins: Vec<String>,
+    pub associations: Vec<AuthorAssociation>,
+}
+
+impl RepoRules {
+    pub fn counts_review(&self, review: &Review) -> bool {
+        let Some(approvers) = &self.approvers else {
+            return true;
+        };
+        let login_allowed = review.user.as_ref().is_some_and(|user| {
+            approvers
+                .logins
+                .iter()
+                .any(|login| matches_wildcard(login, &user.login))
+        });
+        let association_allowed = review
+            .author_association
+            .as_ref()
+            .is_some_and(|association| approvers.associations.contains(association));
+        login_allowed || association_allowed
+    }
+}
+
+// The user's own repo config wins over the team policy, which wins over what GitHub's
+// branch protection requires. Unset values fall through. Within one repo config, a
+// branch rule matching the base branch wins over its `needed_approvals`.
+pub fn resolve_repo_rules(
+    pr_with_reviews: &GithubPRWithReviews,
+    config: &AppConfig,
+    team_policy: &TeamPolicy,
+    team_members: &TeamMembers,
+) -> RepoRules {
+    let layers = matching_layers(pr_with_reviews, config, team_policy);
+
+    let base_ref = pr_with_reviews
+        .pull_request
+        .as_ref()
+        .map(|pr| pr.base.ref_field.as_str());
+
+    RepoRules {
+        needed_approvals: layers
+            .iter()
+            .find_map(|r| layer_needed_approvals(r, base_ref))
+            .or(pr_with_reviews.required_approvals)
+            .unwrap_or(DEFAULT_NEEDED_APPROVALS),
+        dismiss_stale_approvals: layers
+            .iter()
+            .find_map(|r| r.dismiss_stale_approvals)
+            .or(pr_with_reviews.dismiss_stale_reviews)
+            .unwrap_or(DEFAULT_DISMISS_STALE_APPROVALS),
+        approvers: layers
+            .iter()
+            .find(|r| has_approver_allowlist(r))
+            .map(|r| approver_allowlist(pr_with_reviews, r, team_members)),
+        code_owners: pr_with_reviews
+            .code_owners
+            .iter()
+            .map(|owners| code_owner_group(owners, team_members))
+            .collect(),
+    }
+}
+
+// Teams whose members have to be fetched before the PRs can be categorized, from approver
+// allowlists and from CODEOWNERS
+pub fn approver_teams(
+    response: &[GithubPRWithReviews],
+    config: &AppConfig,
+    team_policy: &TeamPolicy,
+) -> Vec<String> {
+    let mut teams: Vec<String> = response
+        .iter()
+        .flat_map(|pr_with_reviews| {
+            let code_owner_teams = pr_with_reviews
+                .code_owners
+                .iter()
+                .flatten()
+                .map(|owner| owner.trim_start_matches('@'))
+                .filter(|owner| owner.contains('/'))
+                .map(|owner| owner.to_string());
+            matching_layers(pr_with_reviews, config, team_policy)
+                .into_iter()
+                .flat_map(|r| qualified_teams(pr_with_reviews, r))
+                .chain(code_owner_teams)
+                .collect::<Vec<String>>()
+        })
+        .collect();
+    teams.sort();
+    teams.dedup();
+    teams
+}
+
+fn matching_layers<'a>(
+    pr_with_reviews: &GithubPRWithReviews,
+    config: &'a AppConfig,
+    team_policy: &'a TeamPolicy,
+) -> Vec<&'a RepoConfigV2> {
+    let repo_name = get_repo_name(pr_with_reviews.pr.repository_url.as_str());
+    config
+        .repo_config
+        .iter()
+        .filter(|r| r.repo_name == repo_name)
+        .chain(
+            team_policy
+                .repos
+                .iter()
+                .filter(|r| matches_wildcard(&r.repo_name, &repo_name)),
+        )
+        .collect()
+}
+
+fn layer_needed_approvals(repo_config: &RepoConfigV2, base_ref: Option<&str>) -> Option<usize> {
+    base_ref
+        .and_then(|base_ref| {
+            repo_config
+                .branch_rules
+                .iter()
+                .find(|rule| matches_wildcard(&rule.branch, base_ref))
+        })
+        .map(|rule| rule.needed_approvals)
+        .or(repo_config.needed_approvals)
+}
+
+// A team whose members are unknown matches nobody, so its approvals don't count until
+// they can be read instead of every approval counting
+fn approver_allowlist(
+    pr_with_reviews: &GithubPRWithReviews,
+    repo_config: &RepoConfigV2,
+    team_members: &TeamMembers,
+) -> ApproverAllowlist {
+    let mut logins = repo_config.approvers.clone();
+    for team in qualified_teams(pr_with_reviews, repo_config) {
+        match team_members.get(&team) {
+            Some(members) => logins.extend(members.iter().cloned()),
+            None => crate::log::error(&format!(
+                "Members of {} are unknown, not counting their approvals on {}",
+                team, repo_config.repo_name
+            )),
+        }
+    }
+    ApproverAllowlist {
+        logins,
+        associations: repo_config.approver_associations.clone(),
+    }
+}
+
+// Only approvals from owners that could be verified satisfy the group
+fn code_owner_group(owners: &[String], team_members: &TeamMembers) -> CodeOwnerGroup {
+    let mut logins = Vec::new();
+    for owner in owners.iter().map(|owner| owner.trim_start_matches('@')) {
+        if !owner.contains('/') {
+            logins.push(owner.to_string());
+            continue;
+        }
+        match team_members.get(owner) {
+            Some(members) => logins.extend(members.iter().cloned()),
+            None => crate::log::error(&format!(
+                "Members of code owner {} are unknown, their approvals don't count",
+                owner
+            )),
+        }
+    }
+    CodeOwnerGroup {
+        owners: owners.to_vec(),
+        logins,
+    }
+}
+
+fn has_approver_allowlist(repo_config: &RepoConfigV2) -> bool {
+    !repo_config.approvers.is_empty()
+        || !repo_config.approver_teams.is_empty()
+        || !repo_config.approver_associations.is_empty()
+}
+
+// Team slugs without an organization belong to the repository owner
+fn qualified_teams(
+    pr_with_reviews: &GithubPRWithReviews,
+    repo_config: &RepoConfigV2,
+) -> Vec<String> {
+    let (owner, _) = get_owner_and_repo(pr_with_reviews.pr.repository_url.as_str());
+    repo_config
+        .approver_teams
+        .iter()
+        .map(|team| {
+            if team.contains('/') {
+                team.clone()
+            } else {
+                format!("{}/{}", owner, team)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,294 @@
+use std::{os::unix::fs::PermissionsExt, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    task::JoinSet,
+};
+
+use crate::{
+    file_storage::get_rpc_socket_path,
+    notifications::{get_status, pause, resume, snooze},
+    polling::refresh_all_filters,
+    settings_file::settings_from_config,
+    AppState,
+};
+
+// Error codes defined by JSON-RPC 2.0, APP_ERROR is for failures of the method itself
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const APP_ERROR: i64 = -32000;
+
+const BIND_RETRY_SECONDS: u64 = 60;
+
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    // None for notifications, which get no response. `"id": null` is still a request.
+    #[serde(default, deserialize_with = "deserialize_id")]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoConfigParams {
+    repo_name: String,
+    #[serde(default)]
+    needed_approvals: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PauseParams {
+    minutes: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SnoozeParams {
+    id: u64,
+    minutes: u64,
+}
+
+// Binds a socket only the current user can connect to, replacing one left behind by a
+// process that didn't shut down cleanly. The socket is created inside a directory only the
+// current user can enter, so there's no window before its own mode is set.
+pub async fn bind_socket(socket_path: &Path) -> Result<UnixListener, String> {
+    let parent_dir = socket_path.parent().unwrap();
+    std::fs::create_dir_all(parent_dir).map_err(|e| e.to_string())?;
+    std::fs::set_permissions(parent_dir, std::fs::Permissions::from_mode(0o700))
+        .map_err(|e| e.to_string())?;
+    if socket_path.exists() {
+        if UnixStream::connect(socket_path).await.is_ok() {
+            return Err(format!(
+                "Another instance is already listening on {}",
+                socket_path.display()
+            ));
+        }
+        std::fs::remove_file(socket_path).map_err(|e| e.to_string())?;
+    }
+    let listener = UnixListener::bind(socket_path).map_err(|e| e.to_string())?;
+    std::fs::set_permissions(socket_path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| e.to_string())?;
+    Ok(listener)
+}
+
+// Line-delimited JSON-RPC 2.0 for scripting the running app, e.g.
+// echo '{"jsonrpc":"2.0","id":1,"method":"refresh"}' | nc -U ~/.pr_sentinel/rpc.sock
+pub async fn start_rpc_job(state: AppState) {
+    // An app that polled while the daemon was down keeps the socket until it notices the
+    // daemon is back, so keep trying
+    let mut interval = tokio::time::interval(Duration::from_secs(BIND_RETRY_SECONDS));
+    let listener = loop {
+        interval.tick().await;
+        match bind_socket(&get_rpc_socket_path()).await {
+            Ok(listener) => break listener,
+            Err(e) => crate::log::error(&format!("Failed to start RPC socket: {}", e)),
+        }
+    };
+
+    // Aborting this job drops the set, which aborts the open connections with it
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    connections.spawn(handle_connection(stream, state.clone()));
+                }
+                Err(e) => crate::log::error(&format!("Failed to accept RPC connection: {}", e)),
+            },
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+        }
+    }
+}
+
+async fn handle_connection(stream: UnixStream, state: AppState) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(response) = handle_line(&state, &line).await else {
+            continue;
+        };
+        let mut output = match serde_json::to_string(&response) {
+            Ok(output) => output,
+            Err(e) => {
+                crate::log::error(&format!("Failed to serialize RPC response: {}", e));
+                break;
+            }
+        };
+        output.push('\n');
+        if writer.write_all(output.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn handle_line(state: &AppState, line: &str) -> Option<RpcResponse> {
+    let request = match serde_json::from_str::<RpcRequest>(line) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+    };
+    if request.jsonrpc != "2.0" {
+        return Some(error_response(
+            request.id.unwrap_or(Value::Null),
+            INVALID_REQUEST,
+            "Only JSON-RPC 2.0 is supported".to_string(),
+        ));
+    }
+
+    let result = call(state, &request.method, request.params).await;
+    if let Err(error) = &result {
+        if request.id.is_none() {
+            crate::log::error(&format!(
+                "RPC notification {} failed: {}",
+                request.method, error.message
+            ));
+        }
+    }
+    let id = request.id?;
+    Some(match result {
+        Ok(result) => RpcResponse {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        },
+        Err(error) => RpcResponse {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(error),
+        },
+    })
+}
+
+// Maps methods onto the same functions the Tauri commands use
+async fn call(state: &AppState, method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "get_data" => to_result(&*state.data.lock().await),
+        "get_config" => to_result(&settings_from_config(&*state.config.lock().await)),
+        "get_notification_status" => to_result(&get_status(state).await),
+        "refresh" => {
+            refresh_all_filters(state.clone()).await;
+            Ok(Value::Null)
+        }
+        "save_repo_config" => {
+            let params: RepoConfigParams = parse_params(params)?;
+            crate::app_state::update_repo_config(state, params.repo_name, params.needed_approvals)
+                .await;
+            crate::app_state::recategorize_last_response(state).await;
+            Ok(Value::Null)
+        }
+        "pause_notifications" => {
+            let params: PauseParams = parse_params(params)?;
+            pause(state, params.minutes)
+                .await
+                .map_err(|message| RpcError {
+                    code: INVALID_PARAMS,
+                    message,
+                })?;
+            Ok(Value::Null)
+        }
+        "resume_notifications" => {
+            resume(state).await;
+            Ok(Value::Null)
+        }
+        "snooze_pull_request" => {
+            let params: SnoozeParams = parse_params(params)?;
+            snooze(state, params.id, params.minutes)
+                .await
+                .map_err(|message| RpcError {
+                    code: INVALID_PARAMS,
+                    message,
+                })?;
+            Ok(Value::Null)
+        }
+        _ => Err(RpcError {
+            code: METHOD_NOT_FOUND,
+            message: format!("Unknown method: {}", method),
+        }),
+    }
+}
+
+// Tells a missing id apart from `"id": null`, which Option<Value> alone doesn't
+fn deserialize_id<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+// Calls a method of the app or daemon running for this user, None when neither is running
+pub async fn call_running_instance(method: &str) -> Option<Result<Value, String>> {
+    let stream = UnixStream::connect(get_rpc_socket_path()).await.ok()?;
+    Some(call_over(stream, method).await)
+}
+
+async fn call_over(stream: UnixStream, method: &str) -> Result<Value, String> {
+    let (reader, mut writer) = stream.into_split();
+    let mut request =
+        serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": method }).to_string();
+    request.push('\n');
+    writer
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let line = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("The running instance closed the connection")?;
+    let mut response: Value = serde_json::from_str(&line).map_err(|e| e.to_string())?;
+    if let Some(error) = response.get("error") {
+        return Err(error["message"].as_str().unwrap_or_default().to_string());
+    }
+    Ok(response["result"].take())
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError {
+        code: INVALID_PARAMS,
+        message: e.to_string(),
+    })
+}
+
+fn to_result<T: Serialize>(value: &T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError {
+        code: APP_ERROR,
+        message: e.to_string(),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: String) -> RpcResponse {
+    RpcResponse {
+        jsonrpc: "2.0",
+        id,
+        result: None,
+        error: Some(RpcError { code, message }),
+    }
+}
@@ -0,0 +1,428 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_data::AppConfig,
+    app_data_v2::{
+        DigestSettingsV2, EmailDigestSettingsV2, MuteRuleV2, NotificationSettingsV2, QuietHoursV2,
+        ReminderRuleV2, RepoConfigV2, WorkingHoursV2,
+    },
+    digest::validate_cadence,
+    http_api::validate_http_api_port,
+    quiet_hours::validate_quiet_hours,
+    reminders::{validate_reminder_rules, validate_working_hours},
+};
+
+// Same as the config schema version, files from other versions are rejected
+const SETTINGS_FILE_VERSION: u8 = 2;
+
+// Settings that can be moved between machines. Secrets stay behind: the GitHub token,
+// the HTTP API token and webhooks, whose URLs carry their own secret. The SMTP password
+// isn't in the config, only where to read it from.
+// Sections missing from a file are left alone on import.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SettingsFile {
+    pub version: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_config: Option<Vec<RepoConfigV2>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mute_rules: Option<Vec<MuteRuleV2>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification_settings: Option<NotificationSettingsV2>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHoursV2>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<DigestSettingsV2>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_digest: Option<EmailDigestSettingsV2>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminder_rules: Option<Vec<ReminderRuleV2>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_hours: Option<WorkingHoursV2>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_api: Option<HttpApiPortSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rereview_on_new_commits: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpApiPortSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+// Merge keeps repo configs and rules that aren't in the file, replace drops them
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    Merge,
+    Replace,
+}
+
+pub fn settings_from_config(config: &AppConfig) -> SettingsFile {
+    SettingsFile {
+        version: SETTINGS_FILE_VERSION,
+        repo_config: Some(config.repo_config.clone()),
+        mute_rules: Some(config.mute_rules.clone()),
+        notification_settings: Some(config.notification_settings.clone()),
+        quiet_hours: Some(config.quiet_hours.clone()),
+        digest: Some(config.digest.clone()),
+        email_digest: Some(config.email_digest.clone()),
+        reminder_rules: Some(config.reminder_rules.clone()),
+        working_hours: Some(config.working_hours.clone()),
+        http_api: Some(HttpApiPortSettings {
+            enabled: config.http_api.enabled,
+            port: config.http_api.port,
+        }),
+        rereview_on_new_commits: Some(config.rereview_on_new_commits),
+    }
+}
+
+pub fn apply_settings(config: &mut AppConfig, settings: SettingsFile, mode: &ImportMode) {
+    if let Some(repo_config) = settings.repo_config {
+        if let ImportMode::Merge = mode {
+            config
+                .repo_config
+                .retain(|r| !repo_config.iter().any(|i| i.repo_name == r.repo_name));
+            config.repo_config.extend(repo_config);
+        } else {
+            config.repo_config = repo_config;
+        }
+    }
+    if let Some(mute_rules) = settings.mute_rules {
+        if let ImportMode::Merge = mode {
+            config
+                .mute_rules
+                .retain(|r| !mute_rules.iter().any(|i| i.id == r.id));
+            config.mute_rules.extend(mute_rules);
+        } else {
+            config.mute_rules = mute_rules;
+        }
+    }
+    if let Some(reminder_rules) = settings.reminder_rules {
+        if let ImportMode::Merge = mode {
+            config
+                .reminder_rules
+                .retain(|r| !reminder_rules.iter().any(|i| i.id == r.id));
+            config.reminder_rules.extend(reminder_rules);
+        } else {
+            config.reminder_rules = reminder_rules;
+        }
+    }
+    if let Some(notification_settings) = settings.notification_settings {
+        config.notification_settings = notification_settings;
+    }
+    if let Some(quiet_hours) = settings.quiet_hours {
+        config.quiet_hours = quiet_hours;
+    }
+    if let Some(digest) = settings.digest {
+        config.digest = digest;
+    }
+    if let Some(email_digest) = settings.email_digest {
+        // Passwords in files exported by older builds are never imported
+        config.email_digest = EmailDigestSettingsV2 {
+            password: None,
+            ..email_digest
+        };
+    }
+    if let Some(working_hours) = settings.working_hours {
+        config.working_hours = working_hours;
+    }
+    if let Some(http_api) = settings.http_api {
+        config.http_api.enabled = http_api.enabled;
+        config.http_api.port = http_api.port;
+    }
+    if let Some(rereview_on_new_commits) = settings.rereview_on_new_commits {
+        config.rereview_on_new_commits = rereview_on_new_commits;
+    }
+}
+
+// Runs the checks of the save commands, nothing is applied when one fails
+pub fn validate_settings(settings: &SettingsFile) -> Result<(), String> {
+    if let Some(reminder_rules) = &settings.reminder_rules {
+        validate_reminder_rules(reminder_rules)?;
+    }
+    if let Some(working_hours) = &settings.working_hours {
+        validate_working_hours(working_hours)?;
+    }
+    if let Some(quiet_hours) = &settings.quiet_hours {
+        validate_quiet_hours(quiet_hours)?;
+    }
+    if let Some(digest) = &settings.digest {
+        validate_cadence(&digest.cadence)?;
+    }
+    if let Some(email_digest) = &settings.email_digest {
+        validate_cadence(&email_digest.cadence)?;
+    }
+    if let Some(http_api) = &settings.http_api {
+        validate_http_api_port(http_api.port)?;
+    }
+    Ok(())
+}
+
+// TOML for .toml files, JSON for everything else
+fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "toml")
+}
+
+pub fn write_settings_file(path: &Path, settings: &SettingsFile) -> Result<(), String> {
+    let contents = if is_toml(path) {
+        toml::to_string_pretty(settings).map_err(|e| e.to_string())?
+    } else {
+        serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?
+    };
+    std::fs::write(path, contents).map_err(|e| e.to_string())
+}
+
+pub fn read_settings_file(path: &Path) -> Result<SettingsFile, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    // Check the version first so old or newer files get a clear error instead of a
+    // complaint about some field
+    let version = if is_toml(path) {
+        toml::from_str::<toml::Table>(&contents)
+            .map_err(|e| e.to_string())?
+            .get("version")
+            .and_then(|v| v.as_integer())
+    } else {
+        serde_json::from_str::<serde_json::Value>(&contents)
+            .map_err(|e| e.to_string())?
+            .get("version")
+            .and_then(|v| v.as_i64())
+    };
+    match version {
+        Some(version) if version == SETTINGS_FILE_VERSION as i64 => {}
+        Some(version) => {
+            return Err(format!(
+                "Settings file has version {}, this version of the app reads version {}",
+                version, SETTINGS_FILE_VERSION
+            ))
+        }
+        None => return Err("Not a settings file, version is missing".to_string()),
+    }
+
+    if is_toml(path) {
+        toml::from_str(&contents).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Weekday;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::app_data_v2::{empty_config, QuietHoursWindowV2};
+
+    // Removed again when dropped
+    struct TempFile(std::path::PathBuf);
+
+    impl TempFile {
+        fn new(extension: &str, contents: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "pr_sentinel_settings_{}.{}",
+                Uuid::new_v4(),
+                extension
+            ));
+            std::fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn repo(repo_name: &str, needed_approvals: usize) -> RepoConfigV2 {
+        RepoConfigV2 {
+            repo_name: repo_name.to_string(),
+            needed_approvals: Some(needed_approvals),
+            branch_rules: Vec::new(),
+            approvers: Vec::new(),
+            approver_teams: Vec::new(),
+            approver_associations: Vec::new(),
+            dismiss_stale_approvals: None,
+        }
+    }
+
+    fn repos(config: &AppConfig) -> Vec<(&str, Option<usize>)> {
+        config
+            .repo_config
+            .iter()
+            .map(|r| (r.repo_name.as_str(), r.needed_approvals))
+            .collect()
+    }
+
+    fn empty_settings() -> SettingsFile {
+        serde_json::from_str(r#"{ "version": 2 }"#).unwrap()
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let cases = [
+            ("json", r#"{ "version": 1 }"#, Some("has version 1")),
+            (
+                "json",
+                r#"{ "version": 3, "future": true }"#,
+                Some("has version 3"),
+            ),
+            (
+                "json",
+                r#"{ "mute_rules": [] }"#,
+                Some("version is missing"),
+            ),
+            ("toml", "version = 1", Some("has version 1")),
+            ("json", r#"{ "version": 2 }"#, None),
+            ("toml", "version = 2", None),
+        ];
+        for (extension, contents, expected_error) in cases {
+            let file = TempFile::new(extension, contents);
+            let result = read_settings_file(&file.0);
+            match expected_error {
+                Some(expected_error) => assert!(
+                    result
+                        .as_ref()
+                        .is_err_and(|error| error.contains(expected_error)),
+                    "{}: {:?}",
+                    contents,
+                    result
+                ),
+                None => assert!(result.is_ok(), "{}: {:?}", contents, result),
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_without_secrets() {
+        let mut config = empty_config();
+        config.github_token = Some("ghp_secret".to_string());
+        config.repo_config = vec![repo("octo/app", 2)];
+        config.mute_rules = vec![MuteRuleV2 {
+            id: Uuid::new_v4(),
+            repo_name: None,
+            author: Some("*[bot]".to_string()),
+            label: None,
+        }];
+        config.quiet_hours = QuietHoursV2 {
+            enabled: true,
+            windows: vec![QuietHoursWindowV2 {
+                days: vec![Weekday::Mon],
+                start: "22:00".to_string(),
+                end: "07:00".to_string(),
+            }],
+        };
+        config.http_api.port = 9000;
+        config.rereview_on_new_commits = false;
+        let exported = settings_from_config(&config);
+
+        for extension in ["json", "toml"] {
+            let file = TempFile::new(extension, "");
+            write_settings_file(&file.0, &exported).unwrap();
+            let contents = std::fs::read_to_string(&file.0).unwrap();
+            assert!(!contents.contains("ghp_secret"), "{}", contents);
+            assert!(!contents.contains(&config.http_api.token), "{}", contents);
+
+            let mut imported = empty_config();
+            apply_settings(
+                &mut imported,
+                read_settings_file(&file.0).unwrap(),
+                &ImportMode::Replace,
+            );
+            assert_eq!(
+                serde_json::to_value(settings_from_config(&imported)).unwrap(),
+                serde_json::to_value(&exported).unwrap(),
+                "{}",
+                extension
+            );
+            assert_eq!(imported.github_token, None);
+        }
+    }
+
+    #[test]
+    fn merge_keeps_entries_missing_from_the_file() {
+        let settings = || SettingsFile {
+            repo_config: Some(vec![repo("octo/web", 3), repo("octo/cli", 1)]),
+            ..empty_settings()
+        };
+        let config = || AppConfig {
+            repo_config: vec![repo("octo/app", 2), repo("octo/web", 2)],
+            ..empty_config()
+        };
+
+        let mut merged = config();
+        apply_settings(&mut merged, settings(), &ImportMode::Merge);
+        assert_eq!(
+            repos(&merged),
+            [
+                ("octo/app", Some(2)),
+                ("octo/web", Some(3)),
+                ("octo/cli", Some(1))
+            ]
+        );
+
+        let mut replaced = config();
+        apply_settings(&mut replaced, settings(), &ImportMode::Replace);
+        assert_eq!(
+            repos(&replaced),
+            [("octo/web", Some(3)), ("octo/cli", Some(1))]
+        );
+    }
+
+    #[test]
+    fn leaves_missing_sections_alone() {
+        for mode in [ImportMode::Merge, ImportMode::Replace] {
+            let mut config = AppConfig {
+                repo_config: vec![repo("octo/app", 2)],
+                ..empty_config()
+            };
+            apply_settings(&mut config, empty_settings(), &mode);
+            assert_eq!(repos(&config), [("octo/app", Some(2))], "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn validates_like_the_save_commands() {
+        let working_hours = WorkingHoursV2 {
+            days: vec![Weekday::Mon],
+            start: "9am".to_string(),
+            end: "17:00".to_string(),
+        };
+        let quiet_hours = QuietHoursV2 {
+            enabled: true,
+            windows: vec![QuietHoursWindowV2 {
+                days: vec![Weekday::Mon],
+                start: "22:00".to_string(),
+                end: "7".to_string(),
+            }],
+        };
+        let invalid = [
+            SettingsFile {
+                working_hours: Some(working_hours),
+                ..empty_settings()
+            },
+            SettingsFile {
+                quiet_hours: Some(quiet_hours),
+                ..empty_settings()
+            },
+            SettingsFile {
+                http_api: Some(HttpApiPortSettings {
+                    enabled: true,
+                    port: 0,
+                }),
+                ..empty_settings()
+            },
+        ];
+        for settings in invalid {
+            assert!(validate_settings(&settings).is_err(), "{:?}", settings);
+        }
+        assert!(validate_settings(&settings_from_config(&empty_config())).is_ok());
+    }
+}
@@ -0,0 +1,59 @@
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{
+    event_bus::AppEvent,
+    event_names::DesktopNotificationPayload,
+    tray::{show_main_window, update_tray},
+    AppState,
+};
+
+// Forwards bus events to the webview under their old Tauri event names, and keeps the
+// tray and window in sync with them
+pub fn forward_events(app_handle: tauri::AppHandle) {
+    let mut events = app_handle.state::<AppState>().events.subscribe();
+    tauri::async_runtime::spawn(async move {
+        while let Some(bus_event) = events.recv().await {
+            let event = bus_event.event;
+            match &event {
+                AppEvent::AppDataUpdated(_) | AppEvent::NotificationStateUpdated(_) => {
+                    update_tray(&app_handle).await;
+                }
+                AppEvent::FocusCategory(_) => show_main_window(&app_handle),
+                AppEvent::ShowNotification(payload) => show_notification(&app_handle, payload),
+                _ => {}
+            }
+            emit_to_webview(&app_handle, &event);
+        }
+    });
+}
+
+fn emit_to_webview(app_handle: &tauri::AppHandle, event: &AppEvent) {
+    let payload = match serde_json::to_value(event) {
+        Ok(mut value) => value["payload"].take(),
+        Err(e) => {
+            crate::log::error(&format!(
+                "Failed to serialize {} event: {}",
+                event.name(),
+                e
+            ));
+            return;
+        }
+    };
+    app_handle.emit(event.name(), payload).unwrap_or_else(|e| {
+        crate::log::error(&format!("Failed to emit {} event: {}", event.name(), e));
+    });
+}
+
+fn show_notification(app_handle: &tauri::AppHandle, payload: &DesktopNotificationPayload) {
+    app_handle
+        .notification()
+        .builder()
+        .title(&payload.title)
+        .body(&payload.body)
+        .auto_cancel()
+        .show()
+        .unwrap_or_else(|e| {
+            crate::log::error(&format!("Failed to send notification: {}", e));
+        });
+}
@@ -0,0 +1,97 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::time;
+
+use crate::{
+    app_data::AppConfig, app_data_v2::RepoConfigV2, app_state::recategorize_last_response, AppState,
+};
+
+const RELOAD_INTERVAL_SECONDS: u64 = 10;
+
+// Repository rules shared by a team, usually a file in a checked out team repository.
+// `repo_name` accepts wildcards like `myorg/*`, the first matching rule wins and the
+// user's own repo config is applied on top.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TeamPolicy {
+    #[serde(default)]
+    pub repos: Vec<RepoConfigV2>,
+}
+
+// TOML for .toml files, JSON for everything else
+pub fn load_team_policy(path: &Path) -> Result<TeamPolicy, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "toml")
+    {
+        toml::from_str(&contents).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    }
+}
+
+// An empty policy when none is configured or the file can't be read
+pub fn load_configured_team_policy(config: &AppConfig) -> TeamPolicy {
+    let Some(path) = &config.team_policy_path else {
+        return TeamPolicy::default();
+    };
+    load_team_policy(Path::new(path)).unwrap_or_else(|e| {
+        crate::log::error(&format!("Failed to load team policy {}: {}", path, e));
+        TeamPolicy::default()
+    })
+}
+
+// Reloads the policy when its path is changed in the settings or the file is modified,
+// and categorizes PRs again with it. A file that can't be read or parsed, e.g. while it's
+// being saved, keeps the last good policy.
+pub async fn start_team_policy_job(state: AppState) {
+    let mut interval = time::interval(Duration::from_secs(RELOAD_INTERVAL_SECONDS));
+    let mut loaded_version = policy_file_version(&state).await;
+
+    loop {
+        interval.tick().await;
+
+        let version = policy_file_version(&state).await;
+        if version == loaded_version {
+            continue;
+        }
+        loaded_version = version;
+
+        let path = state.config.lock().await.team_policy_path.clone();
+        let team_policy = match path {
+            Some(path) => match load_team_policy(Path::new(&path)) {
+                Ok(team_policy) => team_policy,
+                Err(e) => {
+                    crate::log::error(&format!(
+                        "Failed to reload team policy {}, keeping the last one: {}",
+                        path, e
+                    ));
+                    continue;
+                }
+            },
+            None => TeamPolicy::default(),
+        };
+        *state.team_policy.lock().await = team_policy;
+        crate::log::info("Team policy reloaded");
+        recategorize_last_response(&state).await;
+    }
+}
+
+async fn policy_file_version(state: &AppState) -> Option<(PathBuf, Option<SystemTime>)> {
+    let path = state
+        .config
+        .lock()
+        .await
+        .team_policy_path
+        .clone()
+        .map(PathBuf::from)?;
+    let modified = std::fs::metadata(&path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    Some((path, modified))
+}
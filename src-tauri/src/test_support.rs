@@ -0,0 +1,20 @@
+use serde_json::json;
+
+use crate::PullRequestItem;
+
+// A PR in octo/app opened by alice, `category` is the serialized name, e.g. "ReviewRequested"
+pub fn pull_request(id: u64, title: &str, category: &str) -> PullRequestItem {
+    serde_json::from_value(json!({
+        "id": id,
+        "title": title,
+        "repository_url": "https://api.github.com/repos/octo/app",
+        "login": "alice",
+        "avatar_url": "",
+        "url": "",
+        "created_at": "2024-01-01T00:00:00Z",
+        "updated_at": "2024-01-02T00:00:00Z",
+        "html_url": format!("https://github.com/octo/app/pull/{}", id),
+        "category": category,
+    }))
+    .unwrap()
+}
@@ -0,0 +1,194 @@
+use tauri::{
+    menu::{Menu, MenuBuilder, MenuEvent, SubmenuBuilder},
+    tray::TrayIconBuilder,
+    AppHandle, Manager,
+};
+
+use crate::{
+    app_data::PullRequestCategory,
+    commands::{pause_notifications, refresh, resume_notifications},
+    github_service::get_repo_name,
+    notifications::{
+        handle_notification_action, is_paused, NotificationAction, NOTIFICATION_CATEGORIES,
+    },
+    AppState, PullRequestItem,
+};
+
+const TRAY_ID: &str = "main";
+const OPEN_PR_PREFIX: &str = "open-pr:";
+const PAUSE_MINUTES: u64 = 60;
+
+pub fn create_tray(app_handle: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app_handle, &[], false)?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("PR Sentinel")
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app_handle.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app_handle)?;
+    Ok(())
+}
+
+// Rebuilds the menu and counts from the current data
+pub async fn update_tray(app_handle: &AppHandle) {
+    let Some(tray) = app_handle.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let state = app_handle.state::<AppState>();
+    let pull_requests: Vec<PullRequestItem> = state
+        .data
+        .lock()
+        .await
+        .pull_requests
+        .pull_requests
+        .iter()
+        .filter(|pr| !pr.muted)
+        .cloned()
+        .collect();
+    let paused = is_paused(&*state.notification_state.lock().await);
+
+    let summary = format_summary(&pull_requests);
+    let result = build_menu(app_handle, &pull_requests, paused).and_then(|menu| {
+        tray.set_menu(Some(menu))?;
+        tray.set_tooltip(Some(&summary))?;
+        // Tooltips aren't supported by most Linux trays, show the counts next to the icon
+        #[cfg(target_os = "linux")]
+        tray.set_title(Some(&summary))?;
+        Ok(())
+    });
+    result.unwrap_or_else(|e| {
+        crate::log::error(&format!("Failed to update tray: {}", e));
+    });
+}
+
+fn build_menu(
+    app_handle: &AppHandle,
+    pull_requests: &[PullRequestItem],
+    paused: bool,
+) -> tauri::Result<Menu<tauri::Wry>> {
+    let mut builder = MenuBuilder::new(app_handle);
+
+    for category_notification in NOTIFICATION_CATEGORIES.iter() {
+        let in_category: Vec<&PullRequestItem> = pull_requests
+            .iter()
+            .filter(|pr| pr.category == category_notification.category)
+            .collect();
+        if in_category.is_empty() {
+            continue;
+        }
+        let mut submenu = SubmenuBuilder::new(
+            app_handle,
+            format!("{} ({})", category_notification.title, in_category.len()),
+        );
+        for pr in in_category {
+            submenu = submenu.text(
+                format!("{}{}", OPEN_PR_PREFIX, pr.html_url),
+                format!("{} [{}]", pr.title, get_repo_name(&pr.repository_url)),
+            );
+        }
+        builder = builder.item(&submenu.build()?);
+    }
+    if !pull_requests.is_empty() {
+        builder = builder.separator();
+    }
+
+    let (pause_id, pause_text) = if paused {
+        ("resume", "Resume notifications".to_string())
+    } else {
+        (
+            "pause",
+            format!("Pause notifications for {} minutes", PAUSE_MINUTES),
+        )
+    };
+
+    builder
+        .text("show", "Show PR Sentinel")
+        .text("refresh", "Refresh")
+        .text(pause_id, pause_text)
+        .separator()
+        .text("quit", "Quit")
+        .build()
+}
+
+fn handle_menu_event(app_handle: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref();
+    if let Some(url) = id.strip_prefix(OPEN_PR_PREFIX) {
+        handle_notification_action(
+            &app_handle.state::<AppState>(),
+            &NotificationAction::OpenUrl {
+                url: url.to_string(),
+            },
+        );
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    match id {
+        "show" => show_main_window(&app_handle),
+        "refresh" => {
+            tauri::async_runtime::spawn(refresh(app_handle));
+        }
+        "pause" => {
+            tauri::async_runtime::spawn(async move {
+                pause_notifications(PAUSE_MINUTES, app_handle).await.ok();
+            });
+        }
+        "resume" => {
+            tauri::async_runtime::spawn(async move {
+                resume_notifications(app_handle).await.ok();
+            });
+        }
+        "quit" => app_handle.exit(0),
+        _ => {}
+    }
+}
+
+pub fn show_main_window(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        window.show().unwrap_or_else(|e| {
+            crate::log::error(&format!("Failed to show window: {}", e));
+        });
+        window.unminimize().unwrap_or_else(|e| {
+            crate::log::error(&format!("Failed to unminimize window: {}", e));
+        });
+        window.set_focus().unwrap_or_else(|e| {
+            crate::log::error(&format!("Failed to focus window: {}", e));
+        });
+    }
+}
+
+// E.g. "3 to review · 1 approved"
+fn format_summary(pull_requests: &[PullRequestItem]) -> String {
+    let counts: Vec<String> = NOTIFICATION_CATEGORIES
+        .iter()
+        .map(|n| {
+            (
+                n.category.clone(),
+                pull_requests
+                    .iter()
+                    .filter(|pr| pr.category == n.category)
+                    .count(),
+            )
+        })
+        .filter(|(_, count)| *count > 0)
+        .map(|(category, count)| format!("{} {}", count, short_label(&category)))
+        .collect();
+    if counts.is_empty() {
+        return "No open PRs".to_string();
+    }
+    counts.join(" · ")
+}
+
+fn short_label(category: &PullRequestCategory) -> &'static str {
+    match category {
+        PullRequestCategory::MineApproved => "approved",
+        PullRequestCategory::MineChangesRequested => "rejected",
+        PullRequestCategory::MinePending => "pending",
+        PullRequestCategory::ReviewRequested => "to review",
+        PullRequestCategory::Rereview => "to re-review",
+        PullRequestCategory::ReviewMissing => "missing review",
+    }
+}
@@ -0,0 +1,327 @@
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+
+use crate::{
+    app_data_v2::{WebhookConfigV2, WebhookFormatV2},
+    github_service::get_repo_name,
+    notifications::{CategoryTransition, NotificationChannel},
+    PullRequestItem,
+};
+
+const MAX_ATTEMPTS: u32 = 4;
+const INITIAL_BACKOFF_MILLIS: u64 = 1000;
+const REQUEST_TIMEOUT_SECONDS: u64 = 10;
+
+pub struct WebhookChannel {
+    webhook: WebhookConfigV2,
+    client: reqwest::Client,
+    initial_backoff: Duration,
+}
+
+impl WebhookChannel {
+    pub fn new(webhook: WebhookConfigV2) -> WebhookChannel {
+        WebhookChannel {
+            webhook,
+            client: reqwest::Client::new(),
+            initial_backoff: Duration::from_millis(INITIAL_BACKOFF_MILLIS),
+        }
+    }
+
+    // Retries network errors, rate limits and server errors with exponential backoff
+    pub async fn post(&self, payload: &Value) -> Result<String, String> {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .client
+                .post(&self.webhook.url)
+                .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+                .json(payload)
+                .send()
+                .await;
+
+            let error = match result {
+                Ok(response) if response.status().is_success() => {
+                    return Ok(format!("Webhook responded with {}", response.status()));
+                }
+                Ok(response)
+                    if response.status().is_server_error()
+                        || response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    format!("Webhook responded with {}", response.status())
+                }
+                Ok(response) => {
+                    return Err(format!("Webhook responded with {}", response.status()));
+                }
+                Err(e) => e.to_string(),
+            };
+
+            if attempt >= MAX_ATTEMPTS {
+                return Err(format!("{} after {} attempts", error, attempt));
+            }
+            crate::log::error(&format!(
+                "Webhook {} failed, retrying in {:?}: {}",
+                self.webhook.name, backoff, error
+            ));
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+impl NotificationChannel for WebhookChannel {
+    fn name(&self) -> String {
+        format!("webhook {}", self.webhook.name)
+    }
+
+    fn send<'a>(
+        &'a self,
+        transition: &'a CategoryTransition,
+    ) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(async move {
+            let payload = render_payload(
+                &self.webhook.format,
+                transition.category_notification.title,
+                &transition.pull_requests,
+            );
+            self.post(&payload).await
+        })
+    }
+}
+
+pub fn render_payload(
+    format: &WebhookFormatV2,
+    title: &str,
+    pull_requests: &[PullRequestItem],
+) -> Value {
+    match format {
+        WebhookFormatV2::Slack => render_slack(title, pull_requests),
+        WebhookFormatV2::Teams => render_teams(title, pull_requests),
+        WebhookFormatV2::Generic => render_generic(title, pull_requests),
+    }
+}
+
+// Also understood by Mattermost, which accepts Slack compatible payloads
+fn render_slack(title: &str, pull_requests: &[PullRequestItem]) -> Value {
+    let lines: Vec<String> = pull_requests
+        .iter()
+        .map(|pr| {
+            format!(
+                "• <{}|{}> in {} by {}",
+                pr.html_url,
+                pr.title,
+                get_repo_name(&pr.repository_url),
+                pr.login
+            )
+        })
+        .collect();
+    let mut blocks = vec![json!({
+        "type": "header",
+        "text": { "type": "plain_text", "text": title }
+    })];
+    // Slack rejects sections with empty text
+    if !lines.is_empty() {
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": lines.join("\n") }
+        }));
+    }
+    json!({
+        "text": format!("{}: {}", title, pull_requests.len()),
+        "blocks": blocks
+    })
+}
+
+fn render_teams(title: &str, pull_requests: &[PullRequestItem]) -> Value {
+    let mut body = vec![json!({
+        "type": "TextBlock",
+        "size": "Medium",
+        "weight": "Bolder",
+        "text": title
+    })];
+    body.extend(pull_requests.iter().map(|pr| {
+        json!({
+            "type": "TextBlock",
+            "wrap": true,
+            "text": format!(
+                "[{}]({}) in {} by {}",
+                pr.title,
+                pr.html_url,
+                get_repo_name(&pr.repository_url),
+                pr.login
+            )
+        })
+    }));
+    json!({
+        "type": "message",
+        "attachments": [
+            {
+                "contentType": "application/vnd.microsoft.card.adaptive",
+                "content": {
+                    "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                    "type": "AdaptiveCard",
+                    "version": "1.4",
+                    "body": body
+                }
+            }
+        ]
+    })
+}
+
+fn render_generic(title: &str, pull_requests: &[PullRequestItem]) -> Value {
+    json!({
+        "title": title,
+        "pull_requests": pull_requests
+            .iter()
+            .map(|pr| {
+                json!({
+                    "id": pr.id,
+                    "title": pr.title,
+                    "repository": get_repo_name(&pr.repository_url),
+                    "author": pr.login,
+                    "category": pr.category,
+                    "url": pr.html_url,
+                    "updated_at": pr.updated_at,
+                })
+            })
+            .collect::<Vec<Value>>()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::test_support::pull_request;
+
+    type Received = Arc<Mutex<Vec<Value>>>;
+
+    // Local HTTP stand-in answering with the given statuses in turn, repeating the last one
+    async fn serve(statuses: Vec<StatusCode>) -> (WebhookChannel, Received) {
+        let received: Received = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(
+                    |State((statuses, received)): State<(Arc<Vec<StatusCode>>, Received)>,
+                     Json(payload): Json<Value>| async move {
+                        let mut received = received.lock().unwrap();
+                        received.push(payload);
+                        statuses[(received.len() - 1).min(statuses.len() - 1)]
+                    },
+                ),
+            )
+            .with_state((Arc::new(statuses), received.clone()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let channel = WebhookChannel {
+            initial_backoff: Duration::from_millis(10),
+            ..WebhookChannel::new(WebhookConfigV2 {
+                id: Uuid::new_v4(),
+                name: "test".to_string(),
+                url: format!("http://{}/hook", address),
+                format: WebhookFormatV2::Generic,
+                enabled: true,
+            })
+        };
+        (channel, received)
+    }
+
+    #[test]
+    fn renders_slack_blocks() {
+        let payload = render_payload(
+            &WebhookFormatV2::Slack,
+            "Review requested",
+            &[pull_request(1, "Fix login", "ReviewRequested")],
+        );
+        assert_eq!(payload["text"], "Review requested: 1");
+        assert_eq!(payload["blocks"][0]["text"]["text"], "Review requested");
+        assert_eq!(
+            payload["blocks"][1]["text"]["text"],
+            "• <https://github.com/octo/app/pull/1|Fix login> in octo/app by alice"
+        );
+
+        let empty = render_payload(&WebhookFormatV2::Slack, "Review requested", &[]);
+        assert_eq!(empty["blocks"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn renders_teams_card() {
+        let payload = render_payload(
+            &WebhookFormatV2::Teams,
+            "Review requested",
+            &[pull_request(1, "Fix login", "ReviewRequested")],
+        );
+        let body = &payload["attachments"][0]["content"]["body"];
+        assert_eq!(body[0]["text"], "Review requested");
+        assert_eq!(
+            body[1]["text"],
+            "[Fix login](https://github.com/octo/app/pull/1) in octo/app by alice"
+        );
+    }
+
+    #[test]
+    fn renders_generic_json() {
+        let payload = render_payload(
+            &WebhookFormatV2::Generic,
+            "Review requested",
+            &[pull_request(1, "Fix login", "ReviewRequested")],
+        );
+        assert_eq!(payload["title"], "Review requested");
+        assert_eq!(payload["pull_requests"][0]["repository"], "octo/app");
+        assert_eq!(payload["pull_requests"][0]["category"], "ReviewRequested");
+        assert_eq!(
+            payload["pull_requests"][0]["url"],
+            "https://github.com/octo/app/pull/1"
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors_and_rate_limits() {
+        let (channel, received) = serve(vec![
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::OK,
+        ])
+        .await;
+
+        let result = channel.post(&json!({ "text": "hello" })).await;
+
+        assert!(result.is_ok(), "{:?}", result);
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 3);
+        assert!(received.iter().all(|payload| payload["text"] == "hello"));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let (channel, received) = serve(vec![StatusCode::INTERNAL_SERVER_ERROR]).await;
+
+        let result = channel.post(&json!({})).await;
+
+        assert_eq!(
+            result,
+            Err("Webhook responded with 500 Internal Server Error after 4 attempts".to_string())
+        );
+        assert_eq!(received.lock().unwrap().len(), MAX_ATTEMPTS as usize);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (channel, received) = serve(vec![StatusCode::BAD_REQUEST]).await;
+
+        let result = channel.post(&json!({})).await;
+
+        assert!(result.is_err());
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+}
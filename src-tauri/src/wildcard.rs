@@ -0,0 +1,75 @@
+// Simple case-insensitive wildcard matching, `*` matches any sequence and `?` a single character
+pub fn matches_wildcard(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let value: Vec<char> = value.to_lowercase().chars().collect();
+
+    let mut p = 0;
+    let mut v = 0;
+    let mut star: Option<usize> = None;
+    let mut star_value = 0;
+
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some(p);
+            star_value = v;
+            p += 1;
+        } else if let Some(star_index) = star {
+            p = star_index + 1;
+            star_value += 1;
+            v = star_value;
+        } else {
+            return false;
+        }
+    }
+
+    while p < pattern.len() && pattern[p] == '*' {
+        p += 1;
+    }
+
+    p == pattern.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_wildcards() {
+        let cases = [
+            ("octo/app", "octo/app", true),
+            ("octo/app", "octo/apps", false),
+            ("Octo/App", "octo/app", true),
+            ("octo/app", "OCTO/APP", true),
+            ("octo/*", "octo/app", true),
+            ("octo/*", "octo/", true),
+            ("octo/*", "other/app", false),
+            ("*/app", "octo/app", true),
+            ("*", "", true),
+            ("*", "anything", true),
+            ("", "", true),
+            ("", "a", false),
+            ("dependabot*", "dependabot[bot]", true),
+            ("*bot*", "renovate-bot", true),
+            ("*bot*", "alice", false),
+            ("a?c", "abc", true),
+            ("a?c", "ac", false),
+            ("a?c", "abbc", false),
+            ("??", "ab", true),
+            ("a*b*c", "axxbyyc", true),
+            ("a*b*c", "axxbyy", false),
+            ("a**", "a", true),
+        ];
+        for (pattern, value, expected) in cases {
+            assert_eq!(
+                matches_wildcard(pattern, value),
+                expected,
+                "{} against {}",
+                pattern,
+                value
+            );
+        }
+    }
+}
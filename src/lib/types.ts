@@ -2,13 +2,138 @@ export type AppConfig = {
   github_token: string;
   username: string;
   repo_config: RepoConfig[];
+  mute_rules: MuteRule[];
+  notification_settings: NotificationSettings;
+  quiet_hours: QuietHours;
+  digest: DigestSettings;
+  webhooks: WebhookConfig[];
+  email_digest: EmailDigestSettings;
+  reminder_rules: ReminderRule[];
+  working_hours: WorkingHours;
+  http_api: HttpApiSettings;
+  team_policy_path: string | null;
+  rereview_on_new_commits: boolean;
 };
 
 export type RepoConfig = {
   repo_name: string;
+  needed_approvals: number | null;
+  branch_rules: BranchRule[];
+  approvers: string[];
+  approver_teams: string[];
+  approver_associations: AuthorAssociation[];
+  dismiss_stale_approvals: boolean | null;
+};
+
+export type AuthorAssociation =
+  | "COLLABORATOR"
+  | "CONTRIBUTOR"
+  | "FIRST_TIMER"
+  | "FIRST_TIME_CONTRIBUTOR"
+  | "MANNEQUIN"
+  | "MEMBER"
+  | "NONE"
+  | "OWNER";
+
+export type BranchRule = {
+  branch: string;
   needed_approvals: number;
 };
 
+export type MuteRule = {
+  id: string;
+  repo_name: string | null;
+  author: string | null;
+  label: string | null;
+};
+
+export type NotificationSettings = {
+  categories: CategoryNotificationSetting[];
+  repo_overrides: RepoNotificationOverride[];
+};
+
+export type CategoryNotificationSetting = {
+  category: PullRequestCategory;
+  enabled: boolean;
+};
+
+export type RepoNotificationOverride = {
+  repo_name: string;
+  categories: CategoryNotificationSetting[];
+};
+
+export type Weekday = "Mon" | "Tue" | "Wed" | "Thu" | "Fri" | "Sat" | "Sun";
+
+export type QuietHours = {
+  enabled: boolean;
+  windows: QuietHoursWindow[];
+};
+
+export type QuietHoursWindow = {
+  days: Weekday[];
+  start: string;
+  end: string;
+};
+
+export type DigestSettings = {
+  enabled: boolean;
+  cadence: DigestCadence;
+};
+
+export type DigestCadence =
+  | { type: "Interval"; minutes: number }
+  | { type: "Times"; times: string[] };
+
+export type WebhookConfig = {
+  id: string;
+  name: string;
+  url: string;
+  format: "Slack" | "Teams" | "Generic";
+  enabled: boolean;
+};
+
+export type EmailDigestSettings = {
+  enabled: boolean;
+  smtp_host: string;
+  smtp_port: number;
+  starttls: boolean;
+  username: string | null;
+  password_source: SmtpPasswordSource | null;
+  from: string;
+  to: string[];
+  cadence: DigestCadence;
+};
+
+export type SmtpPasswordSource =
+  | { type: "Env"; name: string }
+  | { type: "File"; path: string };
+
+export type ReminderRule = {
+  id: string;
+  categories: PullRequestCategory[];
+  interval_hours: number;
+  escalate_after: number;
+  enabled: boolean;
+};
+
+export type WorkingHours = {
+  days: Weekday[];
+  start: string;
+  end: string;
+};
+
+export type HttpApiSettings = {
+  enabled: boolean;
+  port: number;
+  token: string;
+};
+
+export type NotificationStatus = {
+  paused_until: number | null;
+  quiet_hours_active: boolean;
+  held_count: number;
+};
+
 export type AppData = {
   pull_requests: PullRequestsData;
 };
@@ -30,6 +155,12 @@ export type PullRequestItem = {
   html_url: string;
   is_assigned: boolean;
   category: PullRequestCategory;
+  muted: boolean;
+  base_ref: string | null;
+  head_ref: string | null;
+  pending_code_owners: string[][];
+  is_code_owner: boolean;
+  reason: string | null;
 };
 
 export type PullRequestCategory =
@@ -39,3 +170,7 @@ export type PullRequestCategory =
   | "ReviewRequested"
   | "Rereview"
   | "ReviewMissing";
+
+export type ExportFormat = "csv" | "markdown" | "json";
+
+export type ImportMode = "merge" | "replace";